@@ -1,10 +1,21 @@
 use iced::{
-    widget::{button, column, container, row, scrollable, svg, text, text_input, Column},
-    Command, Length, Renderer,
+    keyboard::Modifiers,
+    widget::{
+        button, column, container, image, row, scrollable, slider, svg, text, text_input, Column,
+        Row,
+    },
+    Color, Command, Length, Renderer,
+};
+use nordify_gui::{
+    palette::{extract, import, Palette},
+    NordifyError,
 };
 use std::{
+    collections::{HashMap, HashSet},
     fs::{self, DirEntry},
+    io,
     path::{Path, PathBuf},
+    time::SystemTime,
 };
 use whatsinaname::AboutFile;
 
@@ -12,6 +23,7 @@ use crate::preview::{ImageView, Previews};
 use crate::Event;
 use crate::{
     menu::Menu,
+    process,
     theme::{self, NordTheme},
 };
 
@@ -20,6 +32,8 @@ pub struct Browser {
     pub addrbar: AddressBar,
     contents: Contents,
     pub selected: String,
+    extraction: Extraction,
+    modifiers: Modifiers,
 }
 
 #[derive(Clone, Debug)]
@@ -30,6 +44,16 @@ pub enum BrowserEvent {
     DelSelected,
     DirUp,
     FocusAddrBar,
+    NordifyFolder(usize),
+    SelectAll,
+    ModifiersChanged(Modifiers),
+    ExtractPalette,
+    SetExtractCount(u8),
+    Extracted(String, Result<Vec<[u8; 3]>, String>),
+    UseExtracted,
+    DirChanged(PathBuf),
+    ToggleGrid,
+    Thumbnail(PathBuf, Result<PathBuf, String>),
 }
 
 impl Browser {
@@ -45,13 +69,18 @@ impl Browser {
             )
             .padding(3)
             .align_y(iced::alignment::Vertical::Center),
-            self.addrbar.view(),
+            self.addrbar.view(self.contents.error.is_none()),
+            button(text(if self.contents.grid { "LIST" } else { "GRID" }).size(16))
+                .on_press(Event::Browser(BrowserEvent::ToggleGrid))
+                .style(theme::ButtonType::MainButton {
+                    btype: theme::MainType::Reset,
+                }),
         ]
         .spacing(12)
         .width(Length::FillPortion(75));
 
         container(
-            column![top_bar, self.contents.view()]
+            column![top_bar, self.contents.view(), self.extraction.view()]
                 .width(Length::FillPortion(75))
                 .padding(10)
                 .spacing(5),
@@ -72,42 +101,68 @@ impl Browser {
             BrowserEvent::AddrChanged(v) => self.addrbar.value = v,
 
             BrowserEvent::AddrSubmit => {
-                if PathBuf::from(&self.addrbar.value).is_dir() {
-                    self.addrbar.addr.clear();
-                    self.addrbar.addr.push(&self.addrbar.value);
-                    self.contents.entries = Contents::get_contents(&self.addrbar.addr);
-                    return self.contents.reset_scroll();
+                let dir = PathBuf::from(&self.addrbar.value);
+                if dir.is_dir() {
+                    if self.open_dir(dir) {
+                        return self.contents.reset_scroll();
+                    }
+                } else {
+                    self.contents.error = Some(ListingError {
+                        path: dir,
+                        kind: io::ErrorKind::NotFound,
+                    });
                 }
             }
             BrowserEvent::ContentClicked(id) => {
                 let entry = &self.contents.entries[id];
                 match entry.ctype {
                     ContentType::Directory => {
-                        let dir = entry.handle.path().to_string_lossy().to_string();
-                        self.contents.entries = Contents::get_contents(&dir);
-                        self.addrbar.addr.clear();
-                        self.addrbar.addr.push(&dir);
-                        self.addrbar.value = dir;
-                        return self.contents.reset_scroll();
+                        let dir = entry.handle.path();
+                        if self.open_dir(dir) {
+                            return self.contents.reset_scroll();
+                        }
                     }
                     ContentType::Image => {
-                        self.contents.clear_selection();
-                        let entry = &mut self.contents.entries[id];
-                        entry.selected = true;
-                        let path = entry.handle.path().display().to_string();
-                        previews.original.set_loc(&path);
-                        self.selected.clear();
-                        self.selected.push_str(&path);
-                        let filename = entry.handle.file_name().to_string_lossy().to_string();
-                        menu.config.filename = format!("{}_nordified.png", filename.get_name());
+                        if self.modifiers.control() {
+                            let entry = &mut self.contents.entries[id];
+                            entry.selected = !entry.selected;
+                            self.contents.anchor = Some(id);
+                        } else if let (true, Some(anchor)) =
+                            (self.modifiers.shift(), self.contents.anchor)
+                        {
+                            self.contents.clear_selection();
+                            self.contents.select_range(anchor.min(id)..=anchor.max(id));
+                        } else {
+                            self.contents.clear_selection();
+                            self.contents.entries[id].selected = true;
+                            self.contents.anchor = Some(id);
+                        }
+
+                        // Deselecting an image moves the focus to the last one still selected
+                        let entry = &self.contents.entries[id];
+                        let focused = if entry.selected {
+                            Some(entry.handle.path())
+                        } else {
+                            self.selection().pop()
+                        };
+                        self.focus(previews, menu, focused);
+                    }
+                    ContentType::Palette => {
+                        let path = entry.handle.path();
+                        match import::import(&path) {
+                            Ok(palette) => menu.add_palette(palette),
+                            Err(e) => menu.report(NordifyError::Import {
+                                path,
+                                reason: e.to_string(),
+                            }),
+                        }
                     }
                     _ => (),
                 }
             }
             BrowserEvent::DirUp => {
-                if self.addrbar.addr.pop() {
-                    self.addrbar.value = String::from(self.addrbar.addr.to_string_lossy());
-                    self.contents.entries = Contents::get_contents(&self.addrbar.addr);
+                if let Some(parent) = self.addrbar.addr.parent() {
+                    self.open_dir(parent.to_path_buf());
                 }
             }
 
@@ -121,13 +176,265 @@ impl Browser {
             }
 
             BrowserEvent::FocusAddrBar => return text_input::focus(self.addrbar.id.clone()),
+
+            BrowserEvent::NordifyFolder(id) => {
+                let entry = &self.contents.entries[id];
+                if let ContentType::Directory = entry.ctype {
+                    return menu.nordify_folder(entry.handle.path(), &self.addrbar.addr);
+                }
+            }
+
+            BrowserEvent::SelectAll => {
+                self.contents.select_range(0..=self.contents.entries.len());
+            }
+
+            BrowserEvent::ModifiersChanged(m) => self.modifiers = m,
+
+            BrowserEvent::ExtractPalette => {
+                if self.selected.has_extension(&crate::EXT) && !self.extraction.busy {
+                    self.extraction.busy = true;
+                    let (path, n) = (self.selected.clone(), self.extraction.count as usize);
+                    return Command::perform(
+                        process::background(move || {
+                            let colors = extract::extract(&path, n).map_err(|e| e.to_string());
+                            (path, colors)
+                        }),
+                        |r| {
+                            let (path, colors) = r.unwrap_or_else(|| {
+                                (String::new(), Err("extraction stopped unexpectedly".into()))
+                            });
+                            Event::Browser(BrowserEvent::Extracted(path, colors))
+                        },
+                    );
+                }
+            }
+
+            BrowserEvent::SetExtractCount(n) => self.extraction.count = n,
+
+            BrowserEvent::Extracted(path, colors) => {
+                self.extraction.busy = false;
+                match colors {
+                    Ok(colors) => {
+                        let filename = PathBuf::from(&path)
+                            .file_name()
+                            .unwrap_or_default()
+                            .to_string_lossy()
+                            .to_string();
+                        self.extraction.palette = Some(Palette::new(
+                            format!("{} ({})", filename.get_name(), colors.len()),
+                            colors,
+                        ));
+                    }
+                    Err(reason) => menu.report(NordifyError::Load {
+                        path: path.into(),
+                        reason,
+                    }),
+                }
+            }
+
+            BrowserEvent::UseExtracted => {
+                if let Some(palette) = &self.extraction.palette {
+                    menu.add_palette(palette.clone());
+                }
+            }
+
+            BrowserEvent::DirChanged(dir) => {
+                if dir == self.addrbar.addr {
+                    self.reload_contents();
+                }
+            }
+
+            BrowserEvent::ToggleGrid => {
+                self.contents.grid = !self.contents.grid;
+                self.contents.queue_thumbnails();
+            }
+
+            BrowserEvent::Thumbnail(path, thumb) => {
+                let key = match &self.contents.queue {
+                    Some((_, keys)) => keys.iter().find(|(p, _)| *p == path).cloned(),
+                    None => None,
+                };
+                if let Some(key) = key {
+                    self.contents
+                        .thumbs
+                        .insert(key, thumb.ok().map(image::Handle::from_path));
+                }
+                let done = match &self.contents.queue {
+                    Some((_, keys)) => keys.iter().all(|k| self.contents.thumbs.contains_key(k)),
+                    None => false,
+                };
+                if done {
+                    self.contents.queue = None;
+                }
+            }
         }
 
         Command::none()
     }
 
+    /// Makes `path` the image that is previewed and saved on its own, or clears it
+    fn focus(&mut self, previews: &mut Previews, menu: &mut Menu, path: Option<PathBuf>) {
+        self.selected.clear();
+        match path {
+            Some(path) => {
+                self.selected.push_str(&path.display().to_string());
+                previews.original.set_loc(&self.selected);
+                let filename = path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string();
+                menu.config.filename = format!("{}_nordified.png", filename.get_name());
+            }
+            None => {
+                previews.original.set_loc("");
+                menu.config.filename.clear();
+            }
+        }
+    }
+
+    /// Every selected image, in listing order
+    pub fn selection(&self) -> Vec<PathBuf> {
+        self.contents
+            .entries
+            .iter()
+            .filter(|e| e.selected)
+            .map(|e| e.handle.path())
+            .collect()
+    }
+
+    /// Re-reads the current directory, keeping the selection and scroll position
     pub fn reload_contents(&mut self) {
-        self.contents.entries = Contents::get_contents(&self.addrbar.addr);
+        match Contents::get_contents(&self.addrbar.addr) {
+            Ok(entries) => {
+                self.contents.merge(entries);
+                self.contents.error = None;
+                self.contents.queue_thumbnails();
+            }
+            Err(e) => self.contents.error = Some(e),
+        }
+    }
+
+    /// Keeps the listing in sync with changes made by other programs
+    pub fn subscription(&self) -> iced::Subscription<Event> {
+        let changes = process::dir_changes(self.addrbar.addr.clone())
+            .map(|dir| Event::Browser(BrowserEvent::DirChanged(dir)));
+        let thumbs = match &self.contents.queue {
+            Some((id, keys)) => {
+                let paths: Vec<_> = keys.iter().map(|(p, _)| p.clone()).collect();
+                process::thumbnails(*id, &paths)
+                    .map(|(path, thumb)| Event::Browser(BrowserEvent::Thumbnail(path, thumb)))
+            }
+            None => iced::Subscription::none(),
+        };
+        iced::Subscription::batch([changes, thumbs])
+    }
+
+    /// Switches the listing over to `dir`, keeping the current listing if it can't be read
+    fn open_dir(&mut self, dir: PathBuf) -> bool {
+        match Contents::get_contents(&dir) {
+            Ok(entries) => {
+                self.contents.entries = entries;
+                self.contents.anchor = None;
+                self.contents.error = None;
+                self.addrbar.value = dir.to_string_lossy().to_string();
+                self.addrbar.addr = dir;
+                self.contents.queue_thumbnails();
+                true
+            }
+            Err(e) => {
+                self.contents.error = Some(e);
+                false
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ListingError {
+    path: PathBuf,
+    kind: io::ErrorKind,
+}
+
+impl std::fmt::Display for ListingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self.kind {
+            io::ErrorKind::PermissionDenied => "Permission denied",
+            io::ErrorKind::NotFound => "Not found",
+            _ => "Unable to read",
+        };
+        write!(f, "{reason}: {}", self.path.display())
+    }
+}
+
+struct Extraction {
+    count: u8,
+    busy: bool,
+    palette: Option<Palette>,
+}
+
+impl Default for Extraction {
+    fn default() -> Self {
+        Extraction {
+            count: 8,
+            busy: false,
+            palette: None,
+        }
+    }
+}
+
+impl Extraction {
+    fn view(&self) -> crate::IcedElement {
+        let swatches: Row<'_, Event, Renderer<NordTheme>> = Row::new().spacing(3);
+        let swatches =
+            self.palette
+                .iter()
+                .flat_map(|p| p.colors.iter())
+                .fold(swatches, |r, [red, g, b]| {
+                    r.push(
+                        container(text(""))
+                            .style(theme::ContainerType::Swatch(Color::from_rgb8(*red, *g, *b)))
+                            .width(Length::Units(16))
+                            .height(Length::Units(20)),
+                    )
+                });
+
+        let extract = button(text(if self.busy { "EXTRACTING" } else { "EXTRACT" }).size(16))
+            .style(theme::ButtonType::MainButton {
+                btype: theme::MainType::Preview,
+            });
+        let extract = if self.busy {
+            extract
+        } else {
+            extract.on_press(Event::Browser(BrowserEvent::ExtractPalette))
+        };
+
+        let mut bar = row![
+            container(text("COLORS").size(16).style(theme::TextType::Label)).padding(3),
+            slider(2..=32, self.count, |n| Event::Browser(
+                BrowserEvent::SetExtractCount(n)
+            ))
+            .width(Length::Units(120)),
+            text(self.count.to_string())
+                .size(16)
+                .width(Length::Units(24)),
+            extract,
+            container(swatches).width(Length::Fill).center_y(),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center);
+
+        if self.palette.is_some() {
+            bar = bar.push(
+                button(text("USE").size(16))
+                    .on_press(Event::Browser(BrowserEvent::UseExtracted))
+                    .style(theme::ButtonType::MainButton {
+                        btype: theme::MainType::Save,
+                    }),
+            );
+        }
+
+        bar.into()
     }
 }
 
@@ -138,12 +445,16 @@ pub struct AddressBar {
 }
 
 impl AddressBar {
-    fn view(&self) -> crate::IcedElement {
+    fn view(&self, valid: bool) -> crate::IcedElement {
         text_input("Directory Location", &self.value, |s| {
             Event::Browser(BrowserEvent::AddrChanged(s))
         })
         .on_submit(Event::Browser(BrowserEvent::AddrSubmit))
-        .style(theme::TextInputType::BrowserBar)
+        .style(if valid {
+            theme::TextInputType::BrowserBar
+        } else {
+            theme::TextInputType::FileName { valid }
+        })
         .size(16)
         .padding(5)
         .id(self.id.clone())
@@ -169,13 +480,39 @@ impl Default for AddressBar {
 struct Contents {
     entries: Vec<Content>,
     scroll_id: scrollable::Id,
+    error: Option<ListingError>,
+    anchor: Option<usize>,
+    grid: bool,
+    /// `None` for images that could not be decoded. Only listed images are kept.
+    thumbs: HashMap<ThumbKey, Option<image::Handle>>,
+    /// Images still waiting for a thumbnail, with the id of the subscription making them
+    queue: Option<(u64, Vec<ThumbKey>)>,
+    next_id: u64,
 }
 
+/// An image and when it was last modified, so edited images get a new thumbnail
+type ThumbKey = (PathBuf, Option<SystemTime>);
+
+/// Cells per row of the grid view
+const GRID_COLUMNS: usize = 4;
+const THUMB_SIDE: u16 = crate::thumbnail::SIZE as u16;
+
 impl Default for Contents {
     fn default() -> Self {
+        let (entries, error) =
+            match Self::get_contents(dirs::home_dir().unwrap_or_default().as_path()) {
+                Ok(entries) => (entries, None),
+                Err(e) => (Vec::new(), Some(e)),
+            };
         Contents {
-            entries: Self::get_contents(dirs::home_dir().unwrap_or_default().as_path()),
+            entries,
             scroll_id: scrollable::Id::unique(),
+            error,
+            anchor: None,
+            grid: false,
+            thumbs: HashMap::new(),
+            queue: None,
+            next_id: 0,
         }
     }
 }
@@ -190,9 +527,34 @@ impl Contents {
         .spacing(10)
         .width(Length::FillPortion(75));
 
+        let col = match &self.error {
+            Some(e) => col.push(
+                container(text(e.to_string()).size(16))
+                    .style(theme::ContainerType::Error)
+                    .padding(4)
+                    .width(Length::Fill),
+            ),
+            None => col,
+        };
+
+        let col = if self.grid {
+            self.entries.chunks(GRID_COLUMNS).fold(col, |c, chunk| {
+                let row = chunk.iter().fold(Row::new().spacing(10), |r, f| {
+                    r.push(f.cell(self.thumbs.get(&f.thumb_key()).cloned().flatten()))
+                });
+                // Pad the last row so its cells keep the same width as the others
+                c.push(
+                    (chunk.len()..GRID_COLUMNS)
+                        .fold(row, |r, _| r.push(container(text("")).width(Length::Fill))),
+                )
+            })
+        } else {
+            self.entries.iter().fold(col, |c, f| c.push(f.view()))
+        };
+
         container(
             scrollable(
-                container(self.entries.iter().fold(col, |c, f| c.push(f.view())))
+                container(col)
                     .padding(20)
                     .style(theme::ContainerType::Inner),
             )
@@ -210,13 +572,79 @@ impl Contents {
         self.entries.iter_mut().for_each(|e| e.selected = false);
     }
 
+    /// Selects the images in `range`, skipping directories and other files
+    fn select_range(&mut self, range: std::ops::RangeInclusive<usize>) {
+        self.entries
+            .iter_mut()
+            .enumerate()
+            .filter(|(i, e)| range.contains(i) && matches!(e.ctype, ContentType::Image))
+            .for_each(|(_, e)| e.selected = true);
+    }
+
+    /// Replaces the entries with a fresh listing of the same directory, carrying the
+    /// selection and range anchor over to entries that are still there
+    fn merge(&mut self, mut entries: Vec<Content>) {
+        let anchor = self
+            .anchor
+            .and_then(|i| self.entries.get(i))
+            .map(|e| e.handle.file_name());
+        let selected: HashSet<_> = self
+            .entries
+            .iter()
+            .filter(|e| e.selected)
+            .map(|e| e.handle.file_name())
+            .collect();
+
+        for entry in &mut entries {
+            entry.selected = selected.contains(&entry.handle.file_name());
+        }
+        self.anchor = anchor.and_then(|a| entries.iter().position(|e| e.handle.file_name() == a));
+        self.entries = entries;
+    }
+
+    /// Forgets thumbnails of images that were removed or changed, and starts generating
+    /// them for listed images that don't have one yet
+    fn queue_thumbnails(&mut self) {
+        let listed: HashSet<_> = self
+            .entries
+            .iter()
+            .filter(|e| matches!(e.ctype, ContentType::Image))
+            .map(Content::thumb_key)
+            .collect();
+        self.thumbs.retain(|k, _| listed.contains(k));
+
+        if !self.grid {
+            self.queue = None;
+            return;
+        }
+        let missing: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|e| matches!(e.ctype, ContentType::Image))
+            .map(Content::thumb_key)
+            .filter(|k| !self.thumbs.contains_key(k))
+            .collect();
+
+        let queued = self.queue.as_ref().map(|(_, keys)| keys);
+        if missing.is_empty() {
+            self.queue = None;
+        } else if queued != Some(&missing) {
+            self.next_id += 1;
+            self.queue = Some((self.next_id, missing));
+        }
+    }
+
     fn reset_scroll(&self) -> Command<Event> {
         scrollable::snap_to(self.scroll_id.clone(), 0.)
     }
 
-    fn get_contents<P: AsRef<Path>>(dir: P) -> Vec<Content> {
+    fn get_contents<P: AsRef<Path>>(dir: P) -> Result<Vec<Content>, ListingError> {
+        let dir = dir.as_ref();
         let (mut files, mut dirs) = fs::read_dir(dir)
-            .unwrap()
+            .map_err(|e| ListingError {
+                path: dir.to_path_buf(),
+                kind: e.kind(),
+            })?
             .filter_map(|r| if let Ok(p) = r { Some(p) } else { None })
             .fold((Vec::new(), Vec::new()), |mut fnd, f| {
                 if f.path().is_dir() {
@@ -230,34 +658,39 @@ impl Contents {
         dirs.sort_by_key(|e| e.file_name());
         files.sort_by_key(|e| e.file_name());
 
-        dirs.into_iter()
+        Ok(dirs
+            .into_iter()
             .chain(files.into_iter())
             .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
             .enumerate()
             .map(|(i, e)| Content::new(e, i))
-            .collect()
+            .collect())
     }
 }
 
 static FOLDER_ICON_SRC: &[u8] = include_bytes!("../media/newfolder.svg");
 static IMAGE_ICON_SRC: &[u8] = include_bytes!("../media/image.svg");
 static FILE_ICON_SRC: &[u8] = include_bytes!("../media/file.svg");
+static PALETTE_ICON_SRC: &[u8] = include_bytes!("../media/palette.svg");
 
 struct Content {
     handle: DirEntry,
     ctype: ContentType,
     id: usize,
     selected: bool,
+    modified: Option<SystemTime>,
 }
 
 impl Content {
     fn new(file: DirEntry, id: usize) -> Self {
+        let modified = file.metadata().and_then(|m| m.modified()).ok();
         if file.path().is_dir() {
             Content {
                 handle: file,
                 ctype: ContentType::Directory,
                 id,
                 selected: false,
+                modified,
             }
         } else if file
             .file_name()
@@ -269,6 +702,15 @@ impl Content {
                 ctype: ContentType::Image,
                 id,
                 selected: false,
+                modified,
+            }
+        } else if import::is_palette_file(&file.path()) {
+            Content {
+                handle: file,
+                ctype: ContentType::Palette,
+                id,
+                selected: false,
+                modified,
             }
         } else {
             Content {
@@ -276,34 +718,32 @@ impl Content {
                 ctype: ContentType::Generic,
                 id,
                 selected: false,
+                modified,
             }
         }
     }
 
+    fn thumb_key(&self) -> ThumbKey {
+        (self.handle.path(), self.modified)
+    }
+
     fn view(&self) -> container::Container<Event, Renderer<NordTheme>> {
         use ContentType::*;
         let btcontent = text(self.handle.file_name().to_string_lossy())
             .size(16)
             .width(Length::FillPortion(1));
 
-        let icon = {
-            let src = match self.ctype {
-                Directory => &FOLDER_ICON_SRC,
-                Image => &IMAGE_ICON_SRC,
-                Generic => &FILE_ICON_SRC,
-            };
-            container(
-                svg::Svg::new(svg::Handle::from_memory(*src))
-                    .content_fit(iced::ContentFit::Contain)
-                    .width(Length::Units(22))
-                    .height(Length::Units(20)),
-            )
-            .padding(2)
-            .center_x()
-            .center_y()
-        };
+        let icon = container(
+            svg::Svg::new(svg::Handle::from_memory(self.icon()))
+                .content_fit(iced::ContentFit::Contain)
+                .width(Length::Units(22))
+                .height(Length::Units(20)),
+        )
+        .padding(2)
+        .center_x()
+        .center_y();
         let button = match self.ctype {
-            Directory | ContentType::Image => button(btcontent)
+            Directory | ContentType::Image | Palette => button(btcontent)
                 .style(theme::ButtonType::Content {
                     selected: self.selected,
                 })
@@ -314,12 +754,77 @@ impl Content {
                 .width(Length::Fill),
         };
 
-        container(row![icon, button].spacing(6)).center_y()
+        let row = row![icon, button].spacing(6);
+        let row = match self.ctype {
+            Directory => row.push(
+                iced::widget::button(text("NORDIFY").size(14))
+                    .on_press(Event::Browser(BrowserEvent::NordifyFolder(self.id)))
+                    .style(theme::ButtonType::MainButton {
+                        btype: theme::MainType::Preview,
+                    }),
+            ),
+            _ => row,
+        };
+
+        container(row.align_items(iced::Alignment::Center)).center_y()
+    }
+
+    /// Grid view of the entry, showing `thumb` in place of the icon when there is one
+    fn cell(&self, thumb: Option<image::Handle>) -> crate::IcedElement {
+        let side = Length::Units(THUMB_SIDE);
+        let preview: crate::IcedElement = match thumb {
+            Some(handle) => image(handle)
+                .width(side)
+                .height(side)
+                .content_fit(iced::ContentFit::Contain)
+                .into(),
+            None => svg::Svg::new(svg::Handle::from_memory(self.icon()))
+                .content_fit(iced::ContentFit::Contain)
+                .width(side)
+                .height(Length::Units(THUMB_SIDE / 2))
+                .into(),
+        };
+
+        let cell = button(
+            column![
+                container(preview)
+                    .width(Length::Fill)
+                    .height(side)
+                    .center_x()
+                    .center_y(),
+                text(self.handle.file_name().to_string_lossy())
+                    .size(13)
+                    .horizontal_alignment(iced::alignment::Horizontal::Center)
+                    .width(Length::Fill),
+            ]
+            .spacing(4),
+        )
+        .style(theme::ButtonType::Content {
+            selected: self.selected,
+        })
+        .padding(4)
+        .width(Length::Fill);
+
+        match self.ctype {
+            ContentType::Generic => cell,
+            _ => cell.on_press(Event::Browser(BrowserEvent::ContentClicked(self.id))),
+        }
+        .into()
+    }
+
+    fn icon(&self) -> &'static [u8] {
+        match self.ctype {
+            ContentType::Directory => FOLDER_ICON_SRC,
+            ContentType::Image => IMAGE_ICON_SRC,
+            ContentType::Palette => PALETTE_ICON_SRC,
+            ContentType::Generic => FILE_ICON_SRC,
+        }
     }
 }
 
 enum ContentType {
     Image,
+    Palette,
     Directory,
     Generic,
 }
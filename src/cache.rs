@@ -0,0 +1,180 @@
+//! Recently rendered previews, so going back to a combination already tried is instant
+
+use nordify_gui::{preview_size, Job, Mode};
+use std::{collections::VecDeque, fs, path::PathBuf, time::SystemTime};
+
+use crate::process::Rendered;
+
+/// Used unless `NORDIFY_PREVIEW_CACHE_MB` says otherwise
+const DEFAULT_BUDGET_MB: usize = 512;
+
+/// Everything a preview depends on
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CacheKey {
+    input: PathBuf,
+    modified: Option<SystemTime>,
+    mode: Mode,
+    /// Only [`Mode::Knn`] uses it, so it is zero for the other modes
+    kval: u8,
+    colors: Vec<[u8; 3]>,
+    /// What the preview is rendered at, rather than the space it was asked to fit, so
+    /// previews of images smaller than the pane survive resizing it
+    size: Option<(u32, u32)>,
+}
+
+impl CacheKey {
+    pub fn new(job: &Job, max: Option<(u32, u32)>) -> Self {
+        CacheKey {
+            input: job.input.clone(),
+            modified: fs::metadata(&job.input).and_then(|m| m.modified()).ok(),
+            mode: job.mode,
+            kval: if job.mode == Mode::Knn { job.kval } else { 0 },
+            colors: job.palette.colors.clone(),
+            size: image::image_dimensions(&job.input)
+                .ok()
+                .map(|size| max.map_or(size, |max| preview_size(size, max))),
+        }
+    }
+}
+
+/// Least recently used previews are dropped once their pixels exceed the budget
+pub struct PreviewCache {
+    budget: usize,
+    used: usize,
+    /// Most recently used last
+    entries: VecDeque<(CacheKey, Rendered)>,
+}
+
+impl Default for PreviewCache {
+    fn default() -> Self {
+        let mb = std::env::var("NORDIFY_PREVIEW_CACHE_MB")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_BUDGET_MB);
+        PreviewCache {
+            budget: mb * 1024 * 1024,
+            used: 0,
+            entries: VecDeque::new(),
+        }
+    }
+}
+
+impl PreviewCache {
+    pub fn get(&mut self, key: &CacheKey) -> Option<Rendered> {
+        let i = self.entries.iter().position(|(k, _)| k == key)?;
+        let entry = self.entries.remove(i)?;
+        let rendered = entry.1.clone();
+        self.entries.push_back(entry);
+        Some(rendered)
+    }
+
+    pub fn insert(&mut self, key: CacheKey, rendered: Rendered) {
+        if let Some(i) = self.entries.iter().position(|(k, _)| *k == key) {
+            if let Some((_, old)) = self.entries.remove(i) {
+                self.used -= size(&old);
+            }
+        }
+        let size = size(&rendered);
+        if size > self.budget {
+            return;
+        }
+
+        self.used += size;
+        self.entries.push_back((key, rendered));
+        while self.used > self.budget {
+            match self.entries.pop_front() {
+                Some((_, old)) => self.used -= self::size(&old),
+                None => break,
+            }
+        }
+    }
+}
+
+/// Bytes held by a preview: its pixels, plus the renderer's copy of them
+fn size(rendered: &Rendered) -> usize {
+    rendered.pixels.as_raw().len() * 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+    use nordify_gui::palette::Palette;
+
+    /// Bytes [`size`] counts for a preview made by [`rendered`]
+    const ENTRY: usize = 4 * 4 * 4 * 2;
+
+    fn cache(entries: usize) -> PreviewCache {
+        PreviewCache {
+            budget: entries * ENTRY,
+            used: 0,
+            entries: VecDeque::new(),
+        }
+    }
+
+    fn key(input: &str) -> CacheKey {
+        let job = Job {
+            input: input.into(),
+            output: PathBuf::new(),
+            mode: Mode::Default,
+            kval: 0,
+            palette: Palette::default(),
+        };
+        CacheKey::new(&job, None)
+    }
+
+    fn rendered() -> Rendered {
+        Rendered::new(RgbaImage::new(4, 4))
+    }
+
+    #[test]
+    fn least_recently_used_go_first() {
+        let mut cache = cache(2);
+        cache.insert(key("a"), rendered());
+        cache.insert(key("b"), rendered());
+        cache.insert(key("c"), rendered());
+
+        assert!(cache.get(&key("a")).is_none());
+        assert!(cache.get(&key("b")).is_some());
+        assert!(cache.get(&key("c")).is_some());
+        assert_eq!(cache.used, 2 * ENTRY);
+    }
+
+    #[test]
+    fn get_makes_an_entry_most_recent() {
+        let mut cache = cache(2);
+        cache.insert(key("a"), rendered());
+        cache.insert(key("b"), rendered());
+        assert!(cache.get(&key("a")).is_some());
+        cache.insert(key("c"), rendered());
+
+        assert!(cache.get(&key("a")).is_some());
+        assert!(cache.get(&key("b")).is_none());
+    }
+
+    #[test]
+    fn inserting_a_key_again_replaces_it() {
+        let mut cache = cache(2);
+        cache.insert(key("a"), rendered());
+        cache.insert(key("b"), rendered());
+        cache.insert(key("a"), rendered());
+        assert_eq!(cache.entries.len(), 2);
+        assert_eq!(cache.used, 2 * ENTRY);
+
+        // "a" is now the most recent, so "b" goes first
+        cache.insert(key("c"), rendered());
+        assert!(cache.get(&key("b")).is_none());
+        assert!(cache.get(&key("a")).is_some());
+    }
+
+    #[test]
+    fn previews_larger_than_the_budget_are_not_kept() {
+        let mut cache = cache(1);
+        cache.insert(key("a"), rendered());
+        cache.insert(key("big"), Rendered::new(RgbaImage::new(8, 8)));
+
+        assert!(cache.get(&key("big")).is_none());
+        assert!(cache.get(&key("a")).is_some());
+        assert_eq!(cache.used, ENTRY);
+    }
+}
@@ -0,0 +1,179 @@
+//! Headless mode: nordify images from the command line without opening a window
+
+use nordify_gui::{
+    folder,
+    palette::{import, Palette},
+    watch::{Watch, WatchEvent},
+    Job, Mode,
+};
+use std::path::{Path, PathBuf};
+
+const USAGE: &str = "\
+Usage: nordify-gui [OPTIONS] <INPUT>...
+       nordify-gui [OPTIONS] --watch <DIR>
+
+Runs without a window when any arguments are given.
+
+Options:
+  -m, --mode <MODE>        default, creative or knn [default: default]
+  -k, --k <K>              neighbours used by the knn mode, 1-255 [default: 32]
+  -p, --palette <PALETTE>  built-in or saved palette name, or a palette file [default: Nord]
+  -o, --output <PATH>      output file, or directory when given several inputs
+                           [default: next to each input]
+  -w, --watch <DIR>        keep nordifying images added to DIR until interrupted,
+                           into --output [default: DIR/nordified]
+  -h, --help               print this message";
+
+struct Args {
+    inputs: Vec<PathBuf>,
+    mode: Mode,
+    kval: u8,
+    palette: Palette,
+    output: Option<PathBuf>,
+    watch: Option<PathBuf>,
+}
+
+/// Returns the process exit code
+pub fn run(args: Vec<String>) -> i32 {
+    let args = match parse(args) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{USAGE}");
+            return 0;
+        }
+        Err(e) => {
+            eprintln!("error: {e}\n\n{USAGE}");
+            return 2;
+        }
+    };
+
+    if let Some(src) = args.watch {
+        return watch(Watch {
+            dst: args.output.unwrap_or_else(|| src.join("nordified")),
+            src,
+            mode: args.mode,
+            kval: args.kval,
+            palette: args.palette,
+        });
+    }
+
+    if let (Some(out), true) = (&args.output, args.inputs.len() > 1) {
+        if let Err(e) = std::fs::create_dir_all(out) {
+            eprintln!("error: could not create {}: {e}", out.display());
+            return 1;
+        }
+    }
+
+    let mut failed = 0;
+    let names = folder::output_names(&args.inputs);
+    for (input, name) in args.inputs.iter().zip(names) {
+        let output = output_path(input, name, args.output.as_deref(), args.inputs.len() > 1);
+        let job = Job {
+            input: input.clone(),
+            output,
+            mode: args.mode,
+            kval: args.kval,
+            palette: args.palette.clone(),
+        };
+        match nordify_gui::run(&job) {
+            Ok(outcome) => println!(
+                "{} -> {} ({:.2}s)",
+                outcome.input.display(),
+                outcome.output.display(),
+                outcome.elapsed.as_secs_f32()
+            ),
+            Err(e) => {
+                eprintln!("{e}");
+                failed += 1;
+            }
+        }
+    }
+
+    i32::from(failed > 0)
+}
+
+fn watch(watch: Watch) -> i32 {
+    println!(
+        "watching {} into {}",
+        watch.src.display(),
+        watch.dst.display()
+    );
+    let result = watch.run(|event| {
+        match event {
+            WatchEvent::Processed(outcome) => println!(
+                "{} -> {} ({:.2}s)",
+                outcome.input.display(),
+                outcome.output.display(),
+                outcome.elapsed.as_secs_f32()
+            ),
+            WatchEvent::Failed(e) => eprintln!("{e}"),
+            WatchEvent::Idle => (),
+        }
+        true
+    });
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("error: {e}");
+            1
+        }
+    }
+}
+
+fn parse(args: Vec<String>) -> Result<Option<Args>, String> {
+    let mut parsed = Args {
+        inputs: Vec::new(),
+        mode: Mode::Default,
+        kval: 32,
+        palette: Palette::default(),
+        output: None,
+        watch: None,
+    };
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {arg}"));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-m" | "--mode" => parsed.mode = value()?.parse()?,
+            "-k" | "--k" => {
+                parsed.kval = value()?
+                    .parse()
+                    .ok()
+                    .filter(|k| *k > 0)
+                    .ok_or("k must be between 1 and 255")?
+            }
+            "-p" | "--palette" => parsed.palette = palette(&value()?)?,
+            "-o" | "--output" => parsed.output = Some(value()?.into()),
+            "-w" | "--watch" => parsed.watch = Some(value()?.into()),
+            s if s.starts_with('-') => return Err(format!("unknown option {s}")),
+            _ => parsed.inputs.push(arg.into()),
+        }
+    }
+
+    match (parsed.inputs.is_empty(), parsed.watch.is_some()) {
+        (true, false) => return Err("no input images given".into()),
+        (false, true) => return Err("--watch takes no input images".into()),
+        _ => (),
+    }
+    Ok(Some(parsed))
+}
+
+/// Looks the palette up by name first, then tries it as a file
+fn palette(name: &str) -> Result<Palette, String> {
+    Palette::available()
+        .into_iter()
+        .find(|p| p.name.eq_ignore_ascii_case(name))
+        .map(Ok)
+        .unwrap_or_else(|| import::import(name).map_err(|e| format!("palette {name}: {e}")))
+}
+
+/// Where `input` is saved, given its output filename `name` from [`folder::output_names`]
+fn output_path(input: &Path, name: String, output: Option<&Path>, many: bool) -> PathBuf {
+    match output {
+        Some(out) if many || out.is_dir() => out.join(name),
+        Some(out) => out.to_path_buf(),
+        None => input.with_file_name(name),
+    }
+}
@@ -0,0 +1,98 @@
+//! Per-pixel colour distance between an image and its nordified version
+
+use image::{
+    imageops::{self, FilterType},
+    Rgba, RgbaImage,
+};
+
+/// Distance at which the heatmap saturates; anything further is plainly a different colour
+const SATURATION: f32 = 50.;
+
+/// Heatmap ramp, from no change to [`SATURATION`]
+const RAMP: [[f32; 3]; 4] = [
+    [46., 52., 64.],
+    [94., 129., 172.],
+    [235., 203., 139.],
+    [191., 97., 106.],
+];
+
+pub struct Difference {
+    pub heatmap: RgbaImage,
+    /// Mean CIE76 ΔE over all pixels
+    pub mean: f32,
+    pub max: f32,
+}
+
+/// Compares `original` with `processed`, resizing the original if their sizes differ
+pub fn difference(original: &RgbaImage, processed: &RgbaImage) -> Difference {
+    let (width, height) = processed.dimensions();
+    let resized;
+    let original = if original.dimensions() == (width, height) {
+        original
+    } else {
+        resized = imageops::resize(original, width, height, FilterType::Triangle);
+        &resized
+    };
+
+    let rgb = |p: &Rgba<u8>| [p.0[0], p.0[1], p.0[2]];
+    let (mut sum, mut max) = (0f64, 0f32);
+    let heatmap = RgbaImage::from_fn(width, height, |x, y| {
+        let d = delta_e(
+            rgb(original.get_pixel(x, y)),
+            rgb(processed.get_pixel(x, y)),
+        );
+        sum += d as f64;
+        max = max.max(d);
+        heat(d)
+    });
+
+    Difference {
+        heatmap,
+        mean: (sum / (width as f64 * height as f64).max(1.)) as f32,
+        max,
+    }
+}
+
+/// Euclidean distance in L*a*b*
+pub fn delta_e(a: [u8; 3], b: [u8; 3]) -> f32 {
+    let (a, b) = (rgb_to_lab(a), rgb_to_lab(b));
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a - b).powi(2))
+        .sum::<f32>()
+        .sqrt()
+}
+
+fn heat(d: f32) -> Rgba<u8> {
+    let t = (d / SATURATION).clamp(0., 1.) * (RAMP.len() - 1) as f32;
+    let i = (t.floor() as usize).min(RAMP.len() - 2);
+    let f = t - i as f32;
+    let c = |ch: usize| (RAMP[i][ch] + (RAMP[i + 1][ch] - RAMP[i][ch]) * f).round() as u8;
+    Rgba([c(0), c(1), c(2), 255])
+}
+
+/// sRGB to CIE L*a*b* (D65)
+fn rgb_to_lab([r, g, b]: [u8; 3]) -> [f32; 3] {
+    let linear = |c: u8| {
+        let c = c as f32 / 255.;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let (r, g, b) = (linear(r), linear(g), linear(b));
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+
+    let f = |t: f32| {
+        if t > (6f32 / 29.).powi(3) {
+            t.cbrt()
+        } else {
+            t / (3. * (6f32 / 29.).powi(2)) + 4. / 29.
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116. * fy - 16., 500. * (fx - fy), 200. * (fy - fz)]
+}
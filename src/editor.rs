@@ -0,0 +1,377 @@
+use iced::{
+    alignment::Horizontal,
+    widget::{button, column, container, row, scrollable, slider, text, text_input, Column, Row},
+    Color, Command, Length, Renderer,
+};
+
+use nordify_gui::{
+    palette::{self, import::parse_hex, Palette},
+    NordifyError,
+};
+
+use crate::{
+    menu::Menu,
+    theme::{self, NordTheme},
+    Event,
+};
+
+const PER_ROW: usize = 8;
+
+#[derive(Default)]
+pub struct Editor {
+    pub open: bool,
+    name: String,
+    swatches: Vec<[u8; 3]>,
+    selected: usize,
+    hex: String,
+    model: Model,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Model {
+    #[default]
+    Rgb,
+    Hsl,
+}
+
+#[derive(Clone, Debug)]
+pub enum EditorEvent {
+    Open,
+    Close,
+    NameChanged(String),
+    Select(usize),
+    Add,
+    Remove,
+    MoveLeft,
+    MoveRight,
+    HexChanged(String),
+    SetRgb(usize, u8),
+    SetHsl(usize, u16),
+    ToggleModel,
+    Save,
+}
+
+impl Editor {
+    pub fn view(&self) -> crate::IcedElement {
+        let top = row![
+            container(text("PALETTE").size(16).style(theme::TextType::Label)).padding(3),
+            text_input("palette name", &self.name, |s| Event::Editor(
+                EditorEvent::NameChanged(s)
+            ))
+            .style(theme::TextInputType::BrowserBar)
+            .size(16)
+            .padding(5),
+            tool("SAVE", EditorEvent::Save, theme::MainType::Save),
+            tool("CLOSE", EditorEvent::Close, theme::MainType::Reset),
+        ]
+        .spacing(12)
+        .align_items(iced::Alignment::Center);
+
+        let tools = row![
+            text_input("#rrggbb", &self.hex, |s| Event::Editor(
+                EditorEvent::HexChanged(s)
+            ))
+            .style(theme::TextInputType::FileName {
+                valid: parse_hex(&self.hex).is_some()
+            })
+            .width(Length::Units(110))
+            .size(16)
+            .padding(5),
+            tool("ADD", EditorEvent::Add, theme::MainType::Save),
+            tool("REMOVE", EditorEvent::Remove, theme::MainType::Preview),
+            tool("<", EditorEvent::MoveLeft, theme::MainType::Reset),
+            tool(">", EditorEvent::MoveRight, theme::MainType::Reset),
+            tool(
+                match self.model {
+                    Model::Rgb => "RGB",
+                    Model::Hsl => "HSL",
+                },
+                EditorEvent::ToggleModel,
+                theme::MainType::Reset
+            ),
+        ]
+        .spacing(8)
+        .align_items(iced::Alignment::Center);
+
+        container(
+            column![top, self.swatches(), tools, self.sliders()]
+                .padding(10)
+                .spacing(8),
+        )
+        .style(theme::ContainerType::Bottom)
+        .width(Length::FillPortion(75))
+        .height(Length::FillPortion(50))
+        .into()
+    }
+
+    pub fn update(&mut self, menu: &mut Menu, event: EditorEvent) -> Command<Event> {
+        match event {
+            EditorEvent::Open => {
+                let palette = menu.palette();
+                self.name = palette.name.clone();
+                self.swatches = palette.colors.clone();
+                self.select(0);
+                self.open = true;
+                return Command::none();
+            }
+            EditorEvent::Close => {
+                self.open = false;
+                return Command::none();
+            }
+            EditorEvent::NameChanged(name) => {
+                self.name = name;
+                return Command::none();
+            }
+            EditorEvent::Select(i) => {
+                self.select(i);
+                return Command::none();
+            }
+            EditorEvent::Save => {
+                let palette = self.palette();
+                // Saved under its name, so it can't be found again without one
+                if palette.name.is_empty() {
+                    menu.report(NordifyError::Save {
+                        path: palette::user_dir().unwrap_or_default(),
+                        reason: "the palette needs a name".into(),
+                    });
+                    return Command::none();
+                }
+                if let Err(e) = palette.save() {
+                    menu.report(NordifyError::Save {
+                        path: palette::user_dir().unwrap_or_default(),
+                        reason: e.to_string(),
+                    });
+                }
+            }
+            EditorEvent::Add => {
+                let color = parse_hex(&self.hex).unwrap_or([0, 0, 0]);
+                let at = (self.selected + 1).min(self.swatches.len());
+                self.swatches.insert(at, color);
+                self.select(at);
+            }
+            EditorEvent::Remove => {
+                if self.selected < self.swatches.len() && self.swatches.len() > 1 {
+                    self.swatches.remove(self.selected);
+                    self.select(self.selected.min(self.swatches.len() - 1));
+                }
+            }
+            EditorEvent::MoveLeft => {
+                if self.selected > 0 && self.selected < self.swatches.len() {
+                    self.swatches.swap(self.selected, self.selected - 1);
+                    self.selected -= 1;
+                }
+            }
+            EditorEvent::MoveRight => {
+                if self.selected + 1 < self.swatches.len() {
+                    self.swatches.swap(self.selected, self.selected + 1);
+                    self.selected += 1;
+                }
+            }
+            EditorEvent::HexChanged(s) => {
+                if let (Some(c), Some(swatch)) =
+                    (parse_hex(&s), self.swatches.get_mut(self.selected))
+                {
+                    *swatch = c;
+                }
+                self.hex = s;
+            }
+            EditorEvent::SetRgb(channel, v) => {
+                if let Some(swatch) = self.swatches.get_mut(self.selected) {
+                    swatch[channel] = v;
+                    self.hex = hex(*swatch);
+                }
+            }
+            EditorEvent::SetHsl(channel, v) => {
+                if let Some(swatch) = self.swatches.get_mut(self.selected) {
+                    let mut hsl = rgb_to_hsl(*swatch);
+                    hsl[channel] = v;
+                    *swatch = hsl_to_rgb(hsl);
+                    self.hex = hex(*swatch);
+                }
+            }
+            EditorEvent::ToggleModel => {
+                self.model = match self.model {
+                    Model::Rgb => Model::Hsl,
+                    Model::Hsl => Model::Rgb,
+                };
+                return Command::none();
+            }
+        }
+
+        // Every edit is applied right away so the next preview reflects it
+        menu.add_palette(self.palette());
+        Command::none()
+    }
+
+    fn palette(&self) -> Palette {
+        Palette::new(self.name.trim(), self.swatches.clone())
+    }
+
+    fn select(&mut self, i: usize) {
+        self.selected = i;
+        self.hex = self.swatches.get(i).map(|c| hex(*c)).unwrap_or_default();
+    }
+
+    fn swatches(&self) -> crate::IcedElement {
+        let col: Column<'_, Event, Renderer<NordTheme>> = Column::new().spacing(6);
+        let rows =
+            self.swatches
+                .chunks(PER_ROW)
+                .enumerate()
+                .fold(col, |col, (r, chunk)| {
+                    col.push(chunk.iter().enumerate().fold(
+                        Row::new().spacing(6),
+                        |row, (c, color)| {
+                            let i = r * PER_ROW + c;
+                            row.push(
+                                button(text(""))
+                                    .on_press(Event::Editor(EditorEvent::Select(i)))
+                                    .style(theme::ButtonType::Swatch {
+                                        color: to_color(*color),
+                                        selected: i == self.selected,
+                                    })
+                                    .width(Length::Units(36))
+                                    .height(Length::Units(28)),
+                            )
+                        },
+                    ))
+                });
+
+        container(scrollable(container(rows).padding(10)))
+            .style(theme::ContainerType::Inner)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+
+    fn sliders(&self) -> crate::IcedElement {
+        let color = match self.swatches.get(self.selected) {
+            Some(color) => color,
+            None => return column![].into(),
+        };
+
+        let rows: Vec<_> = match self.model {
+            Model::Rgb => ["R", "G", "B"]
+                .iter()
+                .enumerate()
+                .map(|(i, label)| {
+                    channel(
+                        label,
+                        color[i].to_string(),
+                        slider(0..=255, color[i], move |v| {
+                            Event::Editor(EditorEvent::SetRgb(i, v))
+                        })
+                        .into(),
+                    )
+                })
+                .collect(),
+            Model::Hsl => {
+                let hsl = rgb_to_hsl(*color);
+                [("H", 360), ("S", 100), ("L", 100)]
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (label, max))| {
+                        channel(
+                            label,
+                            hsl[i].to_string(),
+                            slider(0..=*max, hsl[i], move |v| {
+                                Event::Editor(EditorEvent::SetHsl(i, v))
+                            })
+                            .into(),
+                        )
+                    })
+                    .collect()
+            }
+        };
+
+        container(
+            rows.into_iter()
+                .fold(Column::new().spacing(6), |c, r| c.push(r))
+                .padding(6),
+        )
+        .style(theme::ContainerType::Inner)
+        .width(Length::Fill)
+        .into()
+    }
+}
+
+fn channel<'a>(
+    label: &'static str,
+    value: String,
+    slider: crate::IcedElement<'a>,
+) -> crate::IcedElement<'a> {
+    row![
+        container(text(label).style(theme::TextType::Option).size(18))
+            .width(Length::Units(22))
+            .center_x()
+            .style(theme::ContainerType::Options),
+        slider,
+        text(value).size(16).width(Length::Units(40)),
+    ]
+    .spacing(10)
+    .align_items(iced::Alignment::Center)
+    .into()
+}
+
+fn tool<'a>(label: &'a str, event: EditorEvent, btype: theme::MainType) -> crate::IcedElement<'a> {
+    button(
+        text(label)
+            .size(16)
+            .horizontal_alignment(Horizontal::Center),
+    )
+    .on_press(Event::Editor(event))
+    .style(theme::ButtonType::MainButton { btype })
+    .into()
+}
+
+fn to_color([r, g, b]: [u8; 3]) -> Color {
+    Color::from_rgb8(r, g, b)
+}
+
+fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// Hue in degrees, saturation and lightness in percent
+fn rgb_to_hsl(rgb: [u8; 3]) -> [u16; 3] {
+    let [r, g, b] = rgb.map(|c| c as f32 / 255.);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.;
+    let d = max - min;
+
+    if d == 0. {
+        return [0, 0, (l * 100.).round() as u16];
+    }
+
+    let s = d / (1. - (2. * l - 1.).abs());
+    let h = if max == r {
+        ((g - b) / d).rem_euclid(6.)
+    } else if max == g {
+        (b - r) / d + 2.
+    } else {
+        (r - g) / d + 4.
+    } * 60.;
+
+    [
+        h.round() as u16 % 360,
+        (s * 100.).round() as u16,
+        (l * 100.).round() as u16,
+    ]
+}
+
+fn hsl_to_rgb([h, s, l]: [u16; 3]) -> [u8; 3] {
+    let (h, s, l) = (h as f32 % 360., s as f32 / 100., l as f32 / 100.);
+    let c = (1. - (2. * l - 1.).abs()) * s;
+    let x = c * (1. - ((h / 60.) % 2. - 1.).abs());
+    let m = l - c / 2.;
+    let (r, g, b) = match h as u16 / 60 {
+        0 => (c, x, 0.),
+        1 => (x, c, 0.),
+        2 => (0., c, x),
+        3 => (0., x, c),
+        4 => (x, 0., c),
+        _ => (c, 0., x),
+    };
+    [r, g, b].map(|v| ((v + m) * 255.).round().clamp(0., 255.) as u8)
+}
@@ -0,0 +1,157 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+use whatsinaname::AboutFile;
+
+use crate::{palette::Palette, Job, Mode, NordifyError};
+
+/// Jobs needed to mirror a directory tree into an output root
+#[derive(Clone, Debug, Default)]
+pub struct FolderPlan {
+    pub jobs: Vec<Job>,
+    /// Images whose outputs are newer than the image itself
+    pub skipped: usize,
+}
+
+/// Walks `src` recursively and plans a job for every image that is missing from,
+/// or newer than its counterpart in, `dst`. Output directories are created as needed.
+pub fn plan(
+    src: &Path,
+    dst: &Path,
+    mode: Mode,
+    kval: u8,
+    palette: &Palette,
+) -> Result<FolderPlan, NordifyError> {
+    let mut images = Vec::new();
+    walk(src, dst, &mut images).map_err(|e| NordifyError::Load {
+        path: src.to_path_buf(),
+        reason: e.to_string(),
+    })?;
+    images.sort();
+
+    let mut plan = FolderPlan::default();
+    let names = output_names(&images);
+    for (input, name) in images.into_iter().zip(names) {
+        let rel = input.strip_prefix(src).unwrap_or(&input);
+        let output = dst.join(rel).with_file_name(name);
+
+        if up_to_date(&input, &output) {
+            plan.skipped += 1;
+            continue;
+        }
+
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent).map_err(|e| NordifyError::Save {
+                path: parent.to_path_buf(),
+                reason: e.to_string(),
+            })?;
+        }
+
+        plan.jobs.push(Job {
+            input,
+            output,
+            mode,
+            kval,
+            palette: palette.clone(),
+        });
+    }
+
+    Ok(plan)
+}
+
+/// Output filenames for `inputs`: `<name>_nordified.png`, or `<name>_<ext>_nordified.png`
+/// for images that would otherwise overwrite each other, like `a.jpg` and `a.png`
+pub fn output_names(inputs: &[PathBuf]) -> Vec<String> {
+    let mut stems: HashMap<PathBuf, usize> = HashMap::new();
+    for input in inputs {
+        *stems.entry(input.with_extension("")).or_default() += 1;
+    }
+
+    inputs
+        .iter()
+        .map(|input| {
+            let filename = input
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            let name = filename.get_name();
+            match input.extension() {
+                Some(ext) if stems[&input.with_extension("")] > 1 => {
+                    format!("{name}_{}_nordified.png", ext.to_string_lossy())
+                }
+                _ => format!("{name}_nordified.png"),
+            }
+        })
+        .collect()
+}
+
+/// Outputs may end up inside the tree being walked, so `dst` itself is never entered.
+/// Symlinked directories aren't followed either, as they can loop back to an ancestor.
+fn walk(dir: &Path, dst: &Path, images: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)?.filter_map(Result::ok) {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') || path == dst {
+            continue;
+        }
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            walk(&path, dst, images)?;
+        } else if (file_type.is_file() || path.is_file()) && name.has_extension(&crate::EXT) {
+            images.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn up_to_date(input: &Path, output: &Path) -> bool {
+    let modified = |p: &Path| fs::metadata(p).and_then(|m| m.modified()).ok();
+    match (modified(input), modified(output)) {
+        (Some(i), Some(o)) => o >= i,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn walk_does_not_follow_symlink_loops() {
+        let root = std::env::temp_dir().join(format!("nordify-walk-{}", std::process::id()));
+        let sub = root.join("sub");
+        fs::create_dir_all(&sub).unwrap();
+        fs::write(root.join("a.png"), b"").unwrap();
+        fs::write(sub.join("b.jpg"), b"").unwrap();
+        std::os::unix::fs::symlink(&root, sub.join("loop")).unwrap();
+
+        let mut images = Vec::new();
+        let walked = walk(&root, &root.join("out"), &mut images);
+        images.sort();
+        fs::remove_dir_all(&root).unwrap();
+
+        walked.unwrap();
+        assert_eq!(images, vec![root.join("a.png"), sub.join("b.jpg")]);
+    }
+
+    #[test]
+    fn output_names_keep_extensions_only_when_names_clash() {
+        let inputs: Vec<PathBuf> = ["dir/a.jpg", "dir/a.png", "dir/b.png", "other/a.png"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        assert_eq!(
+            output_names(&inputs),
+            [
+                "a_jpg_nordified.png",
+                "a_png_nordified.png",
+                "b_nordified.png",
+                "a_nordified.png"
+            ]
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Undo and redo of settings
+
+use std::time::{Duration, Instant};
+
+/// Changes of the same kind closer together than this are undone as one,
+/// so dragging a slider or typing a name doesn't flood the history
+const COALESCE: Duration = Duration::from_secs(1);
+
+/// Undo steps kept; older ones are forgotten
+const LIMIT: usize = 100;
+
+pub struct History<T> {
+    undo: Vec<T>,
+    redo: Vec<T>,
+    current: T,
+    /// Kind and time of the last recorded change
+    last: Option<(&'static str, Instant)>,
+}
+
+impl<T: Clone + PartialEq> History<T> {
+    pub fn new(current: T) -> Self {
+        History {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            current,
+            last: None,
+        }
+    }
+
+    pub fn current(&self) -> &T {
+        &self.current
+    }
+
+    /// Records `state` if it differs from the current one. `kind` names what changed.
+    pub fn record(&mut self, state: &T, kind: &'static str) {
+        if *state == self.current {
+            return;
+        }
+        let now = Instant::now();
+        let coalesce = matches!(self.last, Some((k, at)) if k == kind && now - at < COALESCE);
+        if !coalesce {
+            self.undo.push(self.current.clone());
+            if self.undo.len() > LIMIT {
+                self.undo.remove(0);
+            }
+        }
+        self.current = state.clone();
+        self.redo.clear();
+        self.last = Some((kind, now));
+    }
+
+    /// Takes `state` as the current one without making the change undoable
+    pub fn replace(&mut self, state: &T) {
+        self.current = state.clone();
+        self.last = None;
+    }
+
+    pub fn undo(&mut self) -> Option<T> {
+        let previous = self.undo.pop()?;
+        self.redo
+            .push(std::mem::replace(&mut self.current, previous.clone()));
+        self.last = None;
+        Some(previous)
+    }
+
+    pub fn redo(&mut self) -> Option<T> {
+        let next = self.redo.pop()?;
+        self.undo
+            .push(std::mem::replace(&mut self.current, next.clone()));
+        self.last = None;
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pretends the last change happened long enough ago not to be coalesced
+    fn wait(history: &mut History<u8>) {
+        if let Some((_, at)) = &mut history.last {
+            *at -= COALESCE;
+        }
+    }
+
+    #[test]
+    fn undo_and_redo_walk_the_changes() {
+        let mut history = History::new(0);
+        history.record(&1, "a");
+        history.record(&2, "b");
+
+        assert_eq!(history.undo(), Some(1));
+        assert_eq!(history.undo(), Some(0));
+        assert_eq!(history.undo(), None);
+        assert_eq!(history.redo(), Some(1));
+        assert_eq!(history.redo(), Some(2));
+        assert_eq!(history.redo(), None);
+        assert_eq!(*history.current(), 2);
+    }
+
+    #[test]
+    fn recording_clears_redo() {
+        let mut history = History::new(0);
+        history.record(&1, "a");
+        history.undo();
+        history.record(&2, "a");
+
+        assert_eq!(history.redo(), None);
+        assert_eq!(history.undo(), Some(0));
+    }
+
+    #[test]
+    fn quick_changes_of_one_kind_are_one_step() {
+        let mut history = History::new(0);
+        history.record(&1, "a");
+        history.record(&2, "a");
+        history.record(&3, "a");
+        history.record(&4, "b");
+
+        assert_eq!(history.undo(), Some(3));
+        assert_eq!(history.undo(), Some(0));
+        assert_eq!(history.undo(), None);
+    }
+
+    #[test]
+    fn slow_changes_are_separate_steps() {
+        let mut history = History::new(0);
+        history.record(&1, "a");
+        wait(&mut history);
+        history.record(&2, "a");
+
+        assert_eq!(history.undo(), Some(1));
+        assert_eq!(history.undo(), Some(0));
+    }
+
+    #[test]
+    fn unchanged_state_is_not_recorded() {
+        let mut history = History::new(0);
+        history.record(&0, "a");
+        assert_eq!(history.undo(), None);
+    }
+
+    #[test]
+    fn replace_is_not_undoable() {
+        let mut history = History::new(0);
+        history.record(&1, "a");
+        history.replace(&5);
+
+        assert_eq!(*history.current(), 5);
+        assert_eq!(history.undo(), Some(0));
+        assert_eq!(history.redo(), Some(5));
+    }
+
+    #[test]
+    fn oldest_steps_are_forgotten() {
+        let mut history = History::new(0);
+        for i in 1..=LIMIT as u8 + 10 {
+            history.record(&i, "a");
+            wait(&mut history);
+        }
+
+        let mut steps = 0;
+        while history.undo().is_some() {
+            steps += 1;
+        }
+        assert_eq!(steps, LIMIT);
+        assert_eq!(*history.current(), 10);
+    }
+}
@@ -0,0 +1,289 @@
+use image::{imageops::FilterType, DynamicImage, RgbaImage};
+use mapped::{mappers, Mapper, ProcOptions};
+use std::{
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use crate::palette::Palette;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Mode {
+    #[default]
+    Default,
+    Creative,
+    Knn,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 3] = [Self::Default, Self::Creative, Self::Knn];
+}
+
+impl std::fmt::Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Creative => "Creative",
+            Self::Default => "Default",
+            Self::Knn => "kNN",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl std::str::FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Mode::ALL
+            .into_iter()
+            .find(|m| m.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown mode {s}"))
+    }
+}
+
+/// Everything needed to nordify one image
+#[derive(Clone, Debug)]
+pub struct Job {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub mode: Mode,
+    /// Neighbours considered by [`Mode::Knn`], ignored by the other modes
+    pub kval: u8,
+    pub palette: Palette,
+}
+
+/// What a finished [`Job`] did
+#[derive(Clone, Debug)]
+pub struct Outcome {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub mode: Mode,
+    pub palette: String,
+    pub elapsed: Duration,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    Loading,
+    Processing,
+    Saving,
+}
+
+impl std::fmt::Display for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Loading => "Loading",
+            Self::Processing => "Processing",
+            Self::Saving => "Saving",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum NordifyError {
+    Load { path: PathBuf, reason: String },
+    Process { path: PathBuf, reason: String },
+    Save { path: PathBuf, reason: String },
+    Import { path: PathBuf, reason: String },
+    Cancelled { path: PathBuf },
+}
+
+impl NordifyError {
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Load { path, .. }
+            | Self::Process { path, .. }
+            | Self::Save { path, .. }
+            | Self::Import { path, .. }
+            | Self::Cancelled { path } => path,
+        }
+    }
+
+    pub fn reason(&self) -> &str {
+        match self {
+            Self::Load { reason, .. }
+            | Self::Process { reason, .. }
+            | Self::Save { reason, .. }
+            | Self::Import { reason, .. } => reason,
+            Self::Cancelled { .. } => "cancelled",
+        }
+    }
+}
+
+impl std::fmt::Display for NordifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let action = match self {
+            Self::Load { .. } => "load",
+            Self::Process { .. } | Self::Cancelled { .. } => "process",
+            Self::Save { .. } => "save",
+            Self::Import { .. } => "import",
+        };
+        write!(
+            f,
+            "Failed to {action} {}: {}",
+            self.path().display(),
+            self.reason()
+        )
+    }
+}
+
+impl std::error::Error for NordifyError {}
+
+/// Loads, processes and saves the image described by `job` on the current thread
+pub fn run(job: &Job) -> Result<Outcome, NordifyError> {
+    job.execute(|_| true)
+}
+
+impl Job {
+    /// Like [`run`], but calls `progress` before every stage. Returning `false` from it
+    /// abandons the job with [`NordifyError::Cancelled`].
+    pub fn execute(
+        &self,
+        mut progress: impl FnMut(Stage) -> bool,
+    ) -> Result<Outcome, NordifyError> {
+        let start = Instant::now();
+        let image = self.render(&mut progress)?;
+
+        if !progress(Stage::Saving) {
+            return Err(self.cancelled());
+        }
+        // JPEG has no alpha channel
+        let jpeg = self
+            .output
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("jpg") || e.eq_ignore_ascii_case("jpeg"));
+        let saved = if jpeg {
+            DynamicImage::ImageRgba8(image).to_rgb8().save(&self.output)
+        } else {
+            image.save(&self.output)
+        };
+        saved.map_err(|e| NordifyError::Save {
+            path: self.output.clone(),
+            reason: e.to_string(),
+        })?;
+
+        Ok(Outcome {
+            input: self.input.clone(),
+            output: self.output.clone(),
+            mode: self.mode,
+            palette: self.palette.name.clone(),
+            elapsed: start.elapsed(),
+        })
+    }
+
+    /// Loads and processes the image, handing it back instead of saving it.
+    /// `output` is not used.
+    pub fn render(
+        &self,
+        mut progress: impl FnMut(Stage) -> bool,
+    ) -> Result<RgbaImage, NordifyError> {
+        if !progress(Stage::Loading) {
+            return Err(self.cancelled());
+        }
+        let image = self.load()?;
+        self.render_image(image, progress)
+    }
+
+    /// Like [`Job::render`], but images larger than `max` are downscaled to fit it first,
+    /// for quick feedback on large photos
+    pub fn preview(
+        &self,
+        max: (u32, u32),
+        mut progress: impl FnMut(Stage) -> bool,
+    ) -> Result<RgbaImage, NordifyError> {
+        if !progress(Stage::Loading) {
+            return Err(self.cancelled());
+        }
+        let mut image = self.load()?;
+        let (width, height) = preview_size((image.width(), image.height()), max);
+        if (width, height) != (image.width(), image.height()) {
+            image = image.resize_exact(width, height, FilterType::Triangle);
+        }
+        self.render_image(image, progress)
+    }
+
+    /// Processes an image that is already in memory in place of `input`, which is
+    /// only used in errors
+    pub fn render_image(
+        &self,
+        image: DynamicImage,
+        progress: impl FnMut(Stage) -> bool,
+    ) -> Result<RgbaImage, NordifyError> {
+        let colors = self.palette.colors.clone();
+        match self.mode {
+            Mode::Default => nordify(
+                ProcOptions::default().palette(colors),
+                self,
+                image,
+                progress,
+            ),
+            Mode::Creative => nordify(
+                ProcOptions::new(mappers::Creative).palette(colors),
+                self,
+                image,
+                progress,
+            ),
+            Mode::Knn => nordify(
+                ProcOptions::new(mappers::Knn::with(self.kval as usize).memoized()).palette(colors),
+                self,
+                image,
+                progress,
+            ),
+        }
+    }
+
+    fn load(&self) -> Result<DynamicImage, NordifyError> {
+        image::open(&self.input).map_err(|e| NordifyError::Load {
+            path: self.input.clone(),
+            reason: e.to_string(),
+        })
+    }
+
+    fn cancelled(&self) -> NordifyError {
+        NordifyError::Cancelled {
+            path: self.input.clone(),
+        }
+    }
+}
+
+/// Size [`Job::preview`] renders an image of `size` at: scaled down to fit `max` with
+/// its aspect ratio kept, and never scaled up
+pub fn preview_size(size: (u32, u32), max: (u32, u32)) -> (u32, u32) {
+    let (width, height) = size;
+    if width <= max.0 && height <= max.1 {
+        return size;
+    }
+    let ratio = f64::min(max.0 as f64 / width as f64, max.1 as f64 / height as f64);
+    let scale = |side: u32| ((side as f64 * ratio).round() as u32).max(1);
+    (scale(width), scale(height))
+}
+
+fn nordify<M: Mapper>(
+    opts: ProcOptions<M>,
+    job: &Job,
+    image: DynamicImage,
+    mut report: impl FnMut(Stage) -> bool,
+) -> Result<RgbaImage, NordifyError> {
+    if !report(Stage::Processing) {
+        return Err(job.cancelled());
+    }
+    let loaded = opts.load_image(image);
+    let processed = panic::catch_unwind(AssertUnwindSafe(|| loaded.process())).map_err(|e| {
+        NordifyError::Process {
+            path: job.input.clone(),
+            reason: panic_reason(e),
+        }
+    })?;
+
+    Ok(processed.into_image().into_rgba8())
+}
+
+fn panic_reason(payload: Box<dyn std::any::Any + Send>) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown error".into())
+}
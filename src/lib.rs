@@ -0,0 +1,28 @@
+//! Processing core of Nordify, shared by the GUI and the headless command line.
+//!
+//! ```no_run
+//! use nordify_gui::{palette::Palette, Job, Mode};
+//!
+//! let job = Job {
+//!     input: "wallpaper.jpg".into(),
+//!     output: "wallpaper_nordified.png".into(),
+//!     mode: Mode::Knn,
+//!     kval: 12,
+//!     palette: Palette::default(),
+//! };
+//! let outcome = nordify_gui::run(&job)?;
+//! println!("done in {:?}", outcome.elapsed);
+//! # Ok::<(), nordify_gui::NordifyError>(())
+//! ```
+
+pub mod diff;
+pub mod folder;
+mod job;
+pub mod palette;
+pub mod preset;
+pub mod watch;
+
+pub use job::{preview_size, run, Job, Mode, NordifyError, Outcome, Stage};
+
+/// Image extensions that can be nordified
+pub static EXT: [&str; 5] = ["jpg", "jpeg", "png", "bmp", "svg"];
@@ -4,20 +4,32 @@ use iced::widget::{column, row};
 use iced::{executor, Application, Command, Element, Event as KeyEvent, Length, Settings};
 
 mod browser;
+mod cache;
+mod cli;
+mod editor;
+mod history;
 mod menu;
 mod preview;
+mod process;
 mod theme;
+mod thumbnail;
+mod zoom;
 
 use browser::{Browser, BrowserEvent};
+use editor::{Editor, EditorEvent};
 use menu::{Menu, MenuEvent};
-use preview::Previews;
+use preview::{PreviewEvent, Previews};
 use theme::*;
 
-pub static EXT: [&str; 5] = ["jpg", "jpeg", "png", "bmp", "svg"];
+pub use nordify_gui::EXT;
 
 pub type IcedElement<'a> = Element<'a, Event, iced::Renderer<NordTheme>>;
 
 fn main() -> iced::Result {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(args));
+    }
     NordifyGUI::run(Settings::default())
 }
 
@@ -25,6 +37,7 @@ fn main() -> iced::Result {
 struct NordifyGUI {
     previews: Previews,
     browser: Browser,
+    editor: Editor,
     menu: Menu,
     exit: bool,
 }
@@ -33,6 +46,8 @@ struct NordifyGUI {
 pub enum Event {
     Browser(BrowserEvent),
     Menu(MenuEvent),
+    Editor(EditorEvent),
+    Preview(PreviewEvent),
     Quit,
 }
 
@@ -51,41 +66,71 @@ impl Application for NordifyGUI {
     }
 
     fn update(&mut self, message: Self::Message) -> iced::Command<Self::Message> {
-        match message {
+        let command = match message {
             Event::Browser(event) => self
                 .browser
                 .update(&mut self.previews, &mut self.menu, event),
             Event::Menu(event) => self
                 .menu
                 .update(&mut self.previews, &mut self.browser, event),
+            Event::Editor(event) => self.editor.update(&mut self.menu, event),
+            Event::Preview(event) => {
+                self.previews.update(event);
+                Command::none()
+            }
             Event::Quit => {
                 self.exit = true;
                 Command::none()
             }
-        }
+        };
+        self.menu
+            .set_selection(self.browser.selection().len(), &self.browser.addrbar.addr);
+        self.menu.record_history(&self.browser.selected);
+        let auto = self.menu.auto_preview(&self.browser.selected);
+        Command::batch([command, auto])
     }
 
     fn view(&self) -> IcedElement {
         let spacing = 6;
-        column![
+        let left = if self.editor.open {
+            self.editor.view()
+        } else {
+            self.browser.view()
+        };
+        let body = column![
             self.previews.view(),
-            row!(self.browser.view(), self.menu.view(),)
+            row!(left, self.menu.view(),)
                 .spacing(spacing)
                 .height(Length::FillPortion(50))
         ]
         .spacing(spacing)
+        .height(Length::Fill);
+
+        match self.menu.banner() {
+            Some(banner) => column![banner, body],
+            None => column![body],
+        }
+        .spacing(spacing)
         .padding(spacing)
         .into()
     }
 
     fn subscription(&self) -> iced::Subscription<Self::Message> {
-        iced::subscription::events_with(|e, s| match e {
-            KeyEvent::Keyboard(keyboard::Event::KeyPressed {
-                key_code,
-                modifiers,
-            }) => keyboard_event_handler(key_code, modifiers, s),
-            _ => None,
-        })
+        iced::Subscription::batch([
+            iced::subscription::events_with(|e, s| match e {
+                KeyEvent::Keyboard(keyboard::Event::KeyPressed {
+                    key_code,
+                    modifiers,
+                }) => keyboard_event_handler(key_code, modifiers, s),
+                KeyEvent::Keyboard(keyboard::Event::ModifiersChanged(m)) => {
+                    Some(Event::Browser(BrowserEvent::ModifiersChanged(m)))
+                }
+                _ => None,
+            }),
+            self.browser.subscription(),
+            self.previews.subscription(),
+            self.menu.subscription(),
+        ])
     }
 
     fn should_exit(&self) -> bool {
@@ -99,15 +144,22 @@ impl Application for NordifyGUI {
 
 fn keyboard_event_handler(key: KeyCode, modifier: Modifiers, status: Status) -> Option<Event> {
     let basic = || {
-        use menu::MenuEvent::{Preview, Reset, Save};
+        use menu::MenuEvent::{Cancel, Preview, Reset, Save};
         use BrowserEvent::DelSelected;
         match key {
             KeyCode::P => Some(Event::Menu(Preview)),
             KeyCode::R => Some(Event::Menu(Reset)),
             KeyCode::S => Some(Event::Menu(Save)),
+            KeyCode::Escape => Some(Event::Menu(Cancel)),
             KeyCode::Delete => Some(Event::Browser(DelSelected)),
             KeyCode::Q => Some(Event::Quit),
             KeyCode::L => Some(Event::Browser(BrowserEvent::FocusAddrBar)),
+            KeyCode::G => Some(Event::Browser(BrowserEvent::ToggleGrid)),
+            KeyCode::A => Some(Event::Menu(MenuEvent::ToggleAutoPreview)),
+            KeyCode::V => Some(Event::Preview(PreviewEvent::ToggleCompare)),
+            KeyCode::X => Some(Event::Preview(PreviewEvent::Flick)),
+            KeyCode::D => Some(Event::Preview(PreviewEvent::ToggleDifference)),
+            KeyCode::I => Some(Event::Preview(PreviewEvent::ToggleInspector)),
             KeyCode::F => Some(Event::Menu(MenuEvent::FocusFileName)),
             KeyCode::Backspace => Some(Event::Browser(BrowserEvent::DirUp)),
             _ => None,
@@ -125,10 +177,44 @@ fn keyboard_event_handler(key: KeyCode, modifier: Modifiers, status: Status) ->
         }
     };
 
+    // Ctrl+1 applies the first preset, and so on
+    let preset = || {
+        let digits = [
+            KeyCode::Key1,
+            KeyCode::Key2,
+            KeyCode::Key3,
+            KeyCode::Key4,
+            KeyCode::Key5,
+            KeyCode::Key6,
+            KeyCode::Key7,
+            KeyCode::Key8,
+            KeyCode::Key9,
+        ];
+        let i = digits.iter().position(|&k| k == key)?;
+        Some(Event::Menu(MenuEvent::ApplyPreset(i)))
+    };
+
     if modifier.is_empty() && status == Status::Ignored {
         basic().or_else(mode)
     } else {
         match modifier {
+            // Left to a focused text input, rather than falling through to auto-preview
+            Modifiers::CTRL if key == KeyCode::A => {
+                (status == Status::Ignored).then_some(Event::Browser(BrowserEvent::SelectAll))
+            }
+            Modifiers::CTRL if key == KeyCode::Z && status == Status::Ignored => {
+                Some(Event::Menu(MenuEvent::Undo))
+            }
+            m if m == Modifiers::CTRL | Modifiers::SHIFT
+                && key == KeyCode::Z
+                && status == Status::Ignored =>
+            {
+                Some(Event::Menu(MenuEvent::Redo))
+            }
+            Modifiers::SHIFT if key == KeyCode::P && status == Status::Ignored => {
+                Some(Event::Menu(MenuEvent::PreviewFull))
+            }
+            Modifiers::CTRL if status == Status::Ignored => basic().or_else(preset),
             Modifiers::CTRL => basic(),
 
             Modifiers::ALT => mode(),
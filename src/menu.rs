@@ -1,28 +1,71 @@
+use iced::Subscription;
 use iced::{
     alignment::Horizontal,
-    widget::{button, column, container, pick_list, row, slider, text, text_input},
+    widget::{
+        button, column, container, image, pick_list, row, scrollable, slider, text, text_input,
+    },
     Command, Length,
 };
-use mapped::{mappers, Mapper, ProcOptions};
-use std::path::{Path, PathBuf};
-use tempfile::TempDir;
+use nordify_gui::{
+    folder::{self, FolderPlan},
+    palette::Palette,
+    preset::{self, Preset},
+    watch::{Watch, WatchEvent},
+    Job, NordifyError, Stage,
+};
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 use whatsinaname::AboutFile;
 
+pub use nordify_gui::Mode;
+
 use crate::{
     browser::Browser,
-    preview::{ImageView, Previews},
+    cache::{CacheKey, PreviewCache},
+    editor::EditorEvent,
+    history::History,
+    preview::Previews,
+    process::{self, Batch, BatchEvent, JobEvent, JobKind, Rendered, Task, WatchMsg},
     theme, Event,
 };
 
 #[derive(Clone, Debug)]
 pub enum MenuEvent {
     Preview,
+    PreviewFull,
     Save,
     Reset,
     SelectMode(Mode),
+    SelectPalette(Palette),
     FilenameChanged(String),
     FocusFileName,
     SetKVal(UType),
+    Job(JobEvent),
+    Batch(BatchEvent),
+    BatchDirChanged(String),
+    FolderPlanned(Result<FolderPlan, NordifyError>),
+    ToggleWatch,
+    Watch(WatchMsg),
+    CloseBatch,
+    CloseWatch,
+    ToggleAutoPreview,
+    AutoPreview(u64),
+    /// Stops the preview, the saves and any folder run
+    Cancel,
+    CancelPreview,
+    CancelSaves,
+    DismissError,
+    Undo,
+    Redo,
+    /// Goes back to the settings of an earlier preview
+    Restore(usize),
+    PresetNameChanged(String),
+    SavePreset,
+    ApplyPreset(usize),
+    DeletePreset(usize),
 }
 
 #[derive(Clone, Debug)]
@@ -31,41 +74,99 @@ pub enum UType {
     Num(u8),
 }
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
-pub enum Mode {
-    #[default]
-    Default,
-    Creative,
-    Knn,
+pub struct Menu {
+    pub config: Config,
+    filename_id: text_input::Id,
+    palettes: Vec<Palette>,
+    /// The preview being rendered; a newer preview replaces it
+    job: Option<Task>,
+    stage: Option<Stage>,
+    /// Saves run one at a time, oldest first, until they finish or are cancelled
+    saves: VecDeque<Task>,
+    save_stage: Option<Stage>,
+    next_id: u64,
+    error: Option<NordifyError>,
+    selected: usize,
+    batch_dir: String,
+    batch: Option<BatchRun>,
+    watch: Option<WatchRun>,
+    /// Previews automatically when this is set
+    auto: Option<AutoPreview>,
+    cache: PreviewCache,
+    /// Where the preview being rendered goes in the cache
+    pending: Option<(u64, CacheKey)>,
+    /// Settings along with the image they were made for
+    history: History<(String, Config)>,
+    /// Settings of recent previews with a thumbnail of each, oldest first
+    snapshots: Vec<(Config, image::Handle)>,
+    presets: Vec<Preset>,
+    preset_name: String,
 }
 
-impl Mode {
-    const ALL: [Mode; 3] = [Self::Default, Self::Creative, Self::Knn];
+/// Quiet period after the last change before an automatic preview starts
+const AUTO_PREVIEW_DELAY: Duration = Duration::from_millis(200);
+
+#[derive(Default)]
+struct AutoPreview {
+    /// What the last automatic preview was asked for
+    last: Option<(String, Mode, u8, Palette)>,
+    /// Only the most recent change gets previewed
+    generation: u64,
 }
 
-impl std::fmt::Display for Mode {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let name = match self {
-            Self::Creative => "Creative",
-            Self::Default => "Default",
-            Self::Knn => "kNN",
-        };
-        write!(f, "{name}")
-    }
+struct WatchRun {
+    id: u64,
+    watch: Watch,
+    log: Vec<(String, bool)>,
+    active: bool,
 }
 
-pub struct Menu {
-    pub config: Config,
-    temp: TempDir,
-    filename_id: text_input::Id,
+const WATCH_LOG_LEN: usize = 100;
+
+const SNAPSHOTS: usize = 8;
+const SNAPSHOT_SIDE: u32 = 48;
+
+/// Room for about three presets before the list scrolls
+const PRESET_LIST_HEIGHT: u16 = 84;
+
+struct BatchRun {
+    batch: Batch,
+    items: Vec<(String, ItemState)>,
+    /// Images left alone because their outputs were newer
+    skipped: usize,
+    finished: bool,
+}
+
+enum ItemState {
+    Pending,
+    Running(Stage),
+    Saved,
+    Failed(String),
 }
 
 impl Default for Menu {
     fn default() -> Self {
         Menu {
-            temp: tempfile::tempdir().unwrap(),
             config: Default::default(),
             filename_id: text_input::Id::unique(),
+            palettes: Palette::available(),
+            job: None,
+            stage: None,
+            saves: VecDeque::new(),
+            save_stage: None,
+            next_id: 0,
+            error: None,
+            selected: 0,
+            batch_dir: String::new(),
+            batch: None,
+            watch: None,
+            auto: None,
+            cache: PreviewCache::default(),
+            pending: None,
+            history: History::new((String::new(), Config::default())),
+            snapshots: Vec::new(),
+            presets: preset::load(),
+            preset_name: String::new(),
         }
     }
 }
@@ -82,6 +183,37 @@ impl Menu {
             )
             .width(Length::Fill)
             .padding(3),
+            button(
+                text(if self.watching() { "STOP" } else { "WATCH" })
+                    .vertical_alignment(iced::alignment::Vertical::Top)
+                    .size(19)
+            )
+            .on_press(Event::Menu(MenuEvent::ToggleWatch))
+            .style(theme::ButtonType::MainButton {
+                btype: if self.watching() {
+                    theme::MainType::Cancel
+                } else {
+                    theme::MainType::Reset
+                }
+            }),
+            button(
+                text("AUTO")
+                    .vertical_alignment(iced::alignment::Vertical::Top)
+                    .size(19)
+            )
+            .on_press(Event::Menu(MenuEvent::ToggleAutoPreview))
+            .style(theme::ButtonType::Content {
+                selected: self.auto.is_some()
+            }),
+            button(
+                text("FULL RES")
+                    .vertical_alignment(iced::alignment::Vertical::Top)
+                    .size(19)
+            )
+            .on_press(Event::Menu(MenuEvent::PreviewFull))
+            .style(theme::ButtonType::MainButton {
+                btype: theme::MainType::Reset
+            }),
             button(
                 text("PREVIEW")
                     .vertical_alignment(iced::alignment::Vertical::Top)
@@ -100,18 +232,42 @@ impl Menu {
         let modes = pick_list(Mode::ALL.to_vec(), Some(self.config.mode), |m| {
             Event::Menu(MenuEvent::SelectMode(m))
         })
-        .width(Length::Fill);
+        .width(Length::FillPortion(50));
 
-        let filename = text_input("filename", &self.config.filename, |s| {
-            Event::Menu(MenuEvent::FilenameChanged(s))
-        })
-        .width(Length::FillPortion(25))
-        .style(theme::TextInputType::FileName {
-            valid: self.config.filename.is_valid_file_with_ext(&crate::EXT),
-        })
-        .id(self.filename_id.clone())
-        .size(16)
-        .padding(8);
+        let palettes = pick_list(
+            self.palettes.clone(),
+            Some(self.config.palette.clone()),
+            |p| Event::Menu(MenuEvent::SelectPalette(p)),
+        )
+        .width(Length::FillPortion(50));
+
+        let edit = button(text("EDIT").size(16))
+            .on_press(Event::Editor(EditorEvent::Open))
+            .style(theme::ButtonType::MainButton {
+                btype: theme::MainType::Reset,
+            });
+
+        // With several images selected, SAVE writes them all into a directory instead
+        let filename = if self.selected > 1 {
+            text_input("output directory", &self.batch_dir, |s| {
+                Event::Menu(MenuEvent::BatchDirChanged(s))
+            })
+            .style(theme::TextInputType::FileName {
+                valid: !self.batch_dir.is_empty(),
+            })
+        } else {
+            text_input("filename", &self.config.filename, |s| {
+                Event::Menu(MenuEvent::FilenameChanged(s))
+            })
+            .style(theme::TextInputType::FileName {
+                valid: self.config.filename.is_valid_file_with_ext(&crate::EXT),
+            })
+        };
+        let filename = filename
+            .width(Length::FillPortion(25))
+            .id(self.filename_id.clone())
+            .size(16)
+            .padding(8);
 
         let save_reset = row![
             container(
@@ -144,9 +300,20 @@ impl Menu {
         ];
 
         container(
-            column![top, modes, self.options(), filename, save_reset]
-                .padding(10)
-                .spacing(8),
+            column![
+                top,
+                row![modes, palettes, edit]
+                    .spacing(5)
+                    .align_items(iced::Alignment::Center),
+                self.options(),
+                self.status(),
+                self.snapshots_view(),
+                self.presets_view(),
+                filename,
+                save_reset
+            ]
+            .padding(10)
+            .spacing(8),
         )
         .style(theme::ContainerType::Bottom)
         .width(Length::FillPortion(25))
@@ -161,44 +328,26 @@ impl Menu {
         event: MenuEvent,
     ) -> Command<Event> {
         match event {
-            MenuEvent::Preview => {
+            MenuEvent::Preview | MenuEvent::PreviewFull => {
                 if !browser.selected.is_empty() {
-                    let r: String = (0..3).map(|_| fastrand::alphanumeric()).collect();
-                    let loc = format!("{}/nordified{r}.png", self.temp.path().display());
-                    let (file, out): (&Path, &Path) = (browser.selected.as_ref(), loc.as_ref());
-                    previews.nordified.set_loc(&loc);
-                    match self.config.mode {
-                        Mode::Default => nordify(ProcOptions::default(), file, out),
-                        Mode::Creative => nordify(ProcOptions::new(mappers::Creative), file, out),
-                        Mode::Knn => nordify(
-                            ProcOptions::new(
-                                mappers::Knn::with(self.config.kval as usize).memoized(),
-                            ),
-                            file,
-                            out,
-                        ),
-                    }
+                    let max = match event {
+                        MenuEvent::Preview => Some(previews.max_size()),
+                        _ => None,
+                    };
+                    self.preview(previews, &browser.selected, max);
                 }
             }
             MenuEvent::Save => {
-                if !browser.selected.is_empty()
+                let selection = browser.selection();
+                if selection.len() > 1 {
+                    self.start_batch(selection);
+                } else if !browser.selected.is_empty()
                     && self.config.filename.is_valid_file_with_ext(&crate::EXT)
                 {
                     let mut loc = browser.addrbar.addr.to_path_buf();
                     loc.push(&self.config.filename);
-                    let (file, out): (&Path, &Path) = (browser.selected.as_ref(), loc.as_path());
-                    match self.config.mode {
-                        Mode::Default => nordify(ProcOptions::default(), file, out),
-                        Mode::Creative => nordify(ProcOptions::new(mappers::Creative), file, out),
-                        Mode::Knn => nordify(
-                            ProcOptions::new(
-                                mappers::Knn::with(self.config.kval as usize).memoized(),
-                            ),
-                            file,
-                            out,
-                        ),
-                    };
-                    browser.reload_contents();
+                    let task = self.task(JobKind::Save, &browser.selected, loc);
+                    self.saves.push_back(task);
                 }
             }
             MenuEvent::Reset => {
@@ -212,6 +361,8 @@ impl Menu {
             }
             MenuEvent::SelectMode(m) => self.config.mode = m,
 
+            MenuEvent::SelectPalette(p) => self.config.palette = p,
+
             MenuEvent::SetKVal(update) => match update {
                 UType::Num(n) => self.config.kval = n,
                 UType::Text(t) => {
@@ -224,13 +375,801 @@ impl Menu {
             MenuEvent::FilenameChanged(s) => self.config.filename = s,
 
             MenuEvent::FocusFileName => return text_input::focus(self.filename_id.clone()),
+
+            MenuEvent::Job(event) => self.job_update(previews, browser, event),
+
+            MenuEvent::Cancel => {
+                self.job = None;
+                self.stage = None;
+                self.saves.clear();
+                self.save_stage = None;
+                if let Some(run) = &mut self.batch {
+                    run.finished = true;
+                }
+            }
+
+            MenuEvent::CancelPreview => {
+                self.job = None;
+                self.stage = None;
+            }
+
+            MenuEvent::CancelSaves => {
+                self.saves.clear();
+                self.save_stage = None;
+            }
+
+            MenuEvent::DismissError => self.error = None,
+
+            MenuEvent::Undo => {
+                if let Some(state) = self.history.undo() {
+                    self.restore_history(state, &browser.selected);
+                }
+            }
+
+            MenuEvent::Redo => {
+                if let Some(state) = self.history.redo() {
+                    self.restore_history(state, &browser.selected);
+                }
+            }
+
+            MenuEvent::Restore(i) => {
+                if let Some((config, _)) = self.snapshots.get(i) {
+                    // The filename belongs to the image being edited, not the preview
+                    self.config = Config {
+                        filename: self.config.filename.clone(),
+                        ..config.clone()
+                    };
+                    // Cached, unless it has been evicted since
+                    return self.update(previews, browser, MenuEvent::Preview);
+                }
+            }
+
+            MenuEvent::PresetNameChanged(s) => self.preset_name = s,
+
+            MenuEvent::SavePreset => {
+                let name = self.preset_name.trim();
+                if !name.is_empty() {
+                    let preset = Preset {
+                        name: name.to_string(),
+                        mode: self.config.mode,
+                        kval: self.config.kval,
+                        palette: self.config.palette.clone(),
+                    };
+                    // Saving under an existing name overwrites that preset
+                    match self.presets.iter_mut().find(|p| p.name == preset.name) {
+                        Some(p) => *p = preset,
+                        None => self.presets.push(preset),
+                    }
+                    self.preset_name.clear();
+                    self.save_presets();
+                }
+            }
+
+            MenuEvent::ApplyPreset(i) => {
+                if let Some(preset) = self.presets.get(i) {
+                    self.config.mode = preset.mode;
+                    self.config.kval = preset.kval;
+                    let mut palette = preset.palette.clone();
+                    // A listed palette of the same name may have been edited since
+                    if self
+                        .palettes
+                        .iter()
+                        .any(|p| p.name == palette.name && p.colors != palette.colors)
+                    {
+                        palette.name = format!("{} (preset)", palette.name);
+                    }
+                    match self.palettes.iter().position(|p| p.name == palette.name) {
+                        Some(i) => self.palettes[i] = palette.clone(),
+                        None => self.palettes.push(palette.clone()),
+                    }
+                    self.config.palette = palette;
+                }
+            }
+
+            MenuEvent::DeletePreset(i) => {
+                if i < self.presets.len() {
+                    self.presets.remove(i);
+                    self.save_presets();
+                }
+            }
+
+            MenuEvent::Batch(event) => self.batch_update(browser, event),
+
+            MenuEvent::BatchDirChanged(s) => self.batch_dir = s,
+
+            MenuEvent::ToggleWatch => {
+                if self.watching() {
+                    self.watch = None;
+                } else {
+                    let src = browser.addrbar.addr.clone();
+                    let dst = if self.batch_dir.is_empty() {
+                        src.join("nordified")
+                    } else {
+                        PathBuf::from(&self.batch_dir)
+                    };
+                    self.next_id += 1;
+                    self.watch = Some(WatchRun {
+                        id: self.next_id,
+                        log: vec![(
+                            format!("Watching {} into {}", src.display(), dst.display()),
+                            true,
+                        )],
+                        watch: Watch {
+                            src,
+                            dst,
+                            mode: self.config.mode,
+                            kval: self.config.kval,
+                            palette: self.config.palette.clone(),
+                        },
+                        active: true,
+                    });
+                }
+            }
+
+            MenuEvent::Watch(msg) => self.watch_update(browser, msg),
+
+            MenuEvent::ToggleAutoPreview => {
+                self.auto = match self.auto {
+                    Some(_) => None,
+                    None => Some(AutoPreview::default()),
+                }
+            }
+
+            MenuEvent::AutoPreview(generation) => {
+                if self.auto.as_ref().map(|a| a.generation) == Some(generation) {
+                    // Replacing the running job cancels it
+                    return self.update(previews, browser, MenuEvent::Preview);
+                }
+            }
+
+            MenuEvent::FolderPlanned(plan) => match plan {
+                Ok(plan) => self.run_batch(plan.jobs, plan.skipped),
+                Err(e) => self.error = Some(e),
+            },
+
+            MenuEvent::CloseBatch => {
+                if self.batch.as_ref().is_some_and(|b| b.finished) {
+                    self.batch = None;
+                }
+            }
+
+            MenuEvent::CloseWatch => {
+                if !self.watching() {
+                    self.watch = None;
+                }
+            }
         }
 
         Command::none()
     }
 
+    pub fn subscription(&self) -> Subscription<Event> {
+        let job = match &self.job {
+            Some(job) => job.subscription().map(|e| Event::Menu(MenuEvent::Job(e))),
+            None => Subscription::none(),
+        };
+        let save = match self.saves.front() {
+            Some(task) => task.subscription().map(|e| Event::Menu(MenuEvent::Job(e))),
+            None => Subscription::none(),
+        };
+        let batch = match &self.batch {
+            Some(run) if !run.finished => run
+                .batch
+                .subscription()
+                .map(|e| Event::Menu(MenuEvent::Batch(e))),
+            _ => Subscription::none(),
+        };
+        let watch = match &self.watch {
+            Some(run) if run.active => {
+                process::watch(run.id, &run.watch).map(|m| Event::Menu(MenuEvent::Watch(m)))
+            }
+            _ => Subscription::none(),
+        };
+        Subscription::batch([job, save, batch, watch])
+    }
+
+    fn watching(&self) -> bool {
+        self.watch.as_ref().is_some_and(|w| w.active)
+    }
+
+    fn watch_update(&mut self, browser: &mut Browser, msg: WatchMsg) {
+        let run = match &mut self.watch {
+            Some(run) if run.active => run,
+            _ => return,
+        };
+
+        let entry = match msg {
+            WatchMsg::Event(id, event) if id == run.id => match event {
+                WatchEvent::Processed(outcome) => {
+                    if outcome.output.parent() == Some(browser.addrbar.addr.as_path()) {
+                        browser.reload_contents();
+                    }
+                    let name = |p: &Path| {
+                        p.file_name()
+                            .unwrap_or_default()
+                            .to_string_lossy()
+                            .to_string()
+                    };
+                    (
+                        format!("{} -> {}", name(&outcome.input), name(&outcome.output)),
+                        true,
+                    )
+                }
+                WatchEvent::Failed(e) => (e.to_string(), false),
+                WatchEvent::Idle => return,
+            },
+            WatchMsg::Stopped(id, error) if id == run.id => {
+                run.active = false;
+                match error {
+                    Some(e) => (e.to_string(), false),
+                    None => ("Stopped watching".into(), true),
+                }
+            }
+            _ => return,
+        };
+
+        run.log.push(entry);
+        if run.log.len() > WATCH_LOG_LEN {
+            run.log.remove(0);
+        }
+    }
+
+    fn watch_view<'a>(&'a self, run: &'a WatchRun) -> crate::IcedElement<'a> {
+        let log = run
+            .log
+            .iter()
+            .rev()
+            .fold(column![].spacing(2), |col, (line, ok)| {
+                col.push(text(line).size(13).style(if *ok {
+                    theme::TextType::Other
+                } else {
+                    theme::TextType::Failed
+                }))
+            });
+
+        // A stopped watch keeps its log, and the reason it stopped, until closed
+        let body = if run.active {
+            column![scrollable(log).height(Length::Fill)]
+        } else {
+            column![
+                row![
+                    text("Stopped watching").size(14).width(Length::Fill),
+                    button(text("CLOSE").size(14))
+                        .on_press(Event::Menu(MenuEvent::CloseWatch))
+                        .style(theme::ButtonType::MainButton {
+                            btype: theme::MainType::Reset
+                        }),
+                ],
+                scrollable(log).height(Length::Fill),
+            ]
+            .spacing(6)
+        };
+
+        container(body)
+            .style(theme::ContainerType::Inner)
+            .padding(8)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+
+    /// Schedules a preview when auto-preview is on and the image or settings changed
+    /// since the last one
+    pub fn auto_preview(&mut self, selected: &str) -> Command<Event> {
+        let auto = match &mut self.auto {
+            Some(auto) if !selected.is_empty() => auto,
+            _ => return Command::none(),
+        };
+        let wanted = (
+            selected.to_string(),
+            self.config.mode,
+            self.config.kval,
+            self.config.palette.clone(),
+        );
+        if auto.last.as_ref() == Some(&wanted) {
+            return Command::none();
+        }
+
+        auto.last = Some(wanted);
+        auto.generation += 1;
+        let generation = auto.generation;
+        Command::perform(
+            process::background(|| std::thread::sleep(AUTO_PREVIEW_DELAY)),
+            move |_| Event::Menu(MenuEvent::AutoPreview(generation)),
+        )
+    }
+
+    /// Makes the current settings undoable if they changed since the last call. Selecting
+    /// another image changes the filename, but that isn't a change to undo.
+    pub fn record_history(&mut self, selected: &str) {
+        if self.history.current().0 != selected {
+            self.history
+                .replace(&(selected.to_string(), self.config.clone()));
+            return;
+        }
+        let (current, previous) = (&self.config, &self.history.current().1);
+        let kind = if current.mode != previous.mode {
+            "mode"
+        } else if current.kval != previous.kval {
+            "kval"
+        } else if current.palette != previous.palette {
+            "palette"
+        } else {
+            "filename"
+        };
+        self.history
+            .record(&(selected.to_string(), self.config.clone()), kind);
+    }
+
+    /// Applies settings from the history, keeping the filename if they were made for
+    /// another image
+    fn restore_history(&mut self, (image, config): (String, Config), selected: &str) {
+        self.config = if image == selected {
+            config
+        } else {
+            Config {
+                filename: self.config.filename.clone(),
+                ..config
+            }
+        };
+        // Keeps the image, so that a filename typed now is undoable
+        self.history
+            .replace(&(selected.to_string(), self.config.clone()));
+    }
+
+    fn snapshot(&mut self, job: &Job, rendered: &Rendered) {
+        let config = Config {
+            mode: job.mode,
+            filename: String::new(),
+            kval: job.kval,
+            palette: job.palette.clone(),
+        };
+        self.snapshots.retain(|(c, _)| !c.same_settings(&config));
+        self.snapshots
+            .push((config, rendered.thumbnail(SNAPSHOT_SIDE)));
+        if self.snapshots.len() > SNAPSHOTS {
+            self.snapshots.remove(0);
+        }
+    }
+
+    fn snapshots_view(&self) -> crate::IcedElement {
+        self.snapshots
+            .iter()
+            .enumerate()
+            .rev()
+            .fold(row![].spacing(4), |row, (i, (config, handle))| {
+                row.push(
+                    button(
+                        image(handle.clone())
+                            .width(Length::Units(SNAPSHOT_SIDE as u16))
+                            .height(Length::Units(SNAPSHOT_SIDE as u16)),
+                    )
+                    .padding(2)
+                    .on_press(Event::Menu(MenuEvent::Restore(i)))
+                    .style(theme::ButtonType::Content {
+                        selected: config.same_settings(&self.config),
+                    }),
+                )
+            })
+            .into()
+    }
+
+    fn save_presets(&mut self) {
+        if let Err(e) = preset::save(&self.presets) {
+            self.error = Some(NordifyError::Save {
+                path: preset::path().unwrap_or_default(),
+                reason: e.to_string(),
+            });
+        }
+    }
+
+    /// Saved presets, numbered by their Ctrl+digit shortcut, and a field to save the
+    /// current settings as a new one
+    fn presets_view(&self) -> crate::IcedElement {
+        let save = row![
+            text_input("preset name", &self.preset_name, |s| {
+                Event::Menu(MenuEvent::PresetNameChanged(s))
+            })
+            .on_submit(Event::Menu(MenuEvent::SavePreset))
+            .style(theme::TextInputType::BrowserBar)
+            .size(14)
+            .padding(4),
+            button(text("SAVE PRESET").size(14))
+                .on_press(Event::Menu(MenuEvent::SavePreset))
+                .style(theme::ButtonType::MainButton {
+                    btype: theme::MainType::Reset
+                }),
+        ]
+        .spacing(5)
+        .align_items(iced::Alignment::Center);
+
+        let list =
+            self.presets
+                .iter()
+                .enumerate()
+                .fold(column![].spacing(2), |col, (i, preset)| {
+                    let label = match i {
+                        0..=8 => format!("{} {}", i + 1, preset.name),
+                        _ => preset.name.clone(),
+                    };
+                    let applied = self.config.mode == preset.mode
+                        && self.config.kval == preset.kval
+                        && self.config.palette == preset.palette;
+                    col.push(
+                        row![
+                            button(text(label).size(14))
+                                .width(Length::Fill)
+                                .on_press(Event::Menu(MenuEvent::ApplyPreset(i)))
+                                .style(theme::ButtonType::Content { selected: applied }),
+                            button(text("x").size(14))
+                                .on_press(Event::Menu(MenuEvent::DeletePreset(i)))
+                                .style(theme::ButtonType::Content { selected: false }),
+                        ]
+                        .spacing(2),
+                    )
+                });
+
+        column![
+            save,
+            scrollable(list).height(Length::Units(PRESET_LIST_HEIGHT))
+        ]
+        .spacing(4)
+        .into()
+    }
+
+    /// Keeps track of how many images are selected in the browser, defaulting the
+    /// batch output directory to the directory being browsed
+    pub fn set_selection(&mut self, count: usize, dir: &Path) {
+        if count > 1 && self.selected <= 1 && self.batch_dir.is_empty() {
+            self.batch_dir = dir.to_string_lossy().to_string();
+        }
+        self.selected = count;
+    }
+
+    fn start_batch(&mut self, inputs: Vec<PathBuf>) {
+        let dir = PathBuf::from(&self.batch_dir);
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            self.error = Some(NordifyError::Save {
+                path: dir,
+                reason: e.to_string(),
+            });
+            return;
+        }
+
+        let names = folder::output_names(&inputs);
+        let jobs: Vec<Job> = inputs
+            .into_iter()
+            .zip(names)
+            .map(|(input, name)| Job {
+                output: dir.join(name),
+                input,
+                mode: self.config.mode,
+                kval: self.config.kval,
+                palette: self.config.palette.clone(),
+            })
+            .collect();
+
+        self.run_batch(jobs, 0);
+    }
+
+    /// Mirrors `src` into `<output directory>/<name>_nordified`, where the output
+    /// directory defaults to the directory being browsed
+    pub fn nordify_folder(&mut self, src: PathBuf, browsing: &Path) -> Command<Event> {
+        let root = if self.batch_dir.is_empty() {
+            browsing.to_path_buf()
+        } else {
+            PathBuf::from(&self.batch_dir)
+        };
+        let name = src.file_name().unwrap_or_default().to_string_lossy();
+        let dst = root.join(format!("{name}_nordified"));
+        let (mode, kval, palette) = (
+            self.config.mode,
+            self.config.kval,
+            self.config.palette.clone(),
+        );
+
+        let path = src.clone();
+        Command::perform(
+            process::background(move || folder::plan(&src, &dst, mode, kval, &palette)),
+            move |plan| {
+                let plan = plan.unwrap_or_else(|| {
+                    Err(NordifyError::Load {
+                        path: path.clone(),
+                        reason: "stopped unexpectedly".into(),
+                    })
+                });
+                Event::Menu(MenuEvent::FolderPlanned(plan))
+            },
+        )
+    }
+
+    fn run_batch(&mut self, jobs: Vec<Job>, skipped: usize) {
+        self.next_id += 1;
+        self.batch = Some(BatchRun {
+            items: jobs
+                .iter()
+                .map(|j| {
+                    let name = j.input.file_name().unwrap_or_default();
+                    (name.to_string_lossy().to_string(), ItemState::Pending)
+                })
+                .collect(),
+            batch: Batch {
+                id: self.next_id,
+                jobs,
+            },
+            skipped,
+            finished: false,
+        });
+    }
+
+    fn batch_update(&mut self, browser: &mut Browser, event: BatchEvent) {
+        let run = match &mut self.batch {
+            Some(run) if !run.finished => run,
+            _ => return,
+        };
+
+        match event {
+            BatchEvent::Progress(id, i, stage) if id == run.batch.id => {
+                if let Some(item) = run.items.get_mut(i) {
+                    item.1 = ItemState::Running(stage);
+                }
+            }
+            BatchEvent::Done(id, i, result) if id == run.batch.id => {
+                if let Some(item) = run.items.get_mut(i) {
+                    item.1 = match result {
+                        Ok(_) => ItemState::Saved,
+                        Err(e) => ItemState::Failed(e.reason().to_string()),
+                    };
+                }
+            }
+            BatchEvent::Finished(id) if id == run.batch.id => {
+                run.finished = true;
+                browser.reload_contents();
+            }
+            _ => (),
+        }
+    }
+
+    fn batch_view<'a>(&'a self, run: &'a BatchRun) -> crate::IcedElement<'a> {
+        let saved = run
+            .items
+            .iter()
+            .filter(|(_, s)| matches!(s, ItemState::Saved))
+            .count();
+        let failed = run
+            .items
+            .iter()
+            .filter(|(_, s)| matches!(s, ItemState::Failed(_)))
+            .count();
+        let skipped = match run.skipped {
+            0 => String::new(),
+            n => format!(", {n} already up to date, skipped"),
+        };
+
+        let header = if run.finished {
+            row![
+                text(format!(
+                    "Saved {saved} of {}, {failed} failed{skipped}",
+                    run.items.len()
+                ))
+                .size(14)
+                .width(Length::Fill),
+                button(text("CLOSE").size(14))
+                    .on_press(Event::Menu(MenuEvent::CloseBatch))
+                    .style(theme::ButtonType::MainButton {
+                        btype: theme::MainType::Reset
+                    }),
+            ]
+        } else {
+            row![text(format!(
+                "Saving {} of {}{skipped}...",
+                (saved + failed + 1).min(run.items.len()),
+                run.items.len()
+            ))
+            .size(14)
+            .width(Length::Fill)]
+        };
+
+        let list = run
+            .items
+            .iter()
+            .fold(column![].spacing(2), |col, (name, state)| {
+                let (status, style) = match state {
+                    ItemState::Pending => ("waiting".to_string(), theme::TextType::Label),
+                    ItemState::Running(stage) => (stage.to_string(), theme::TextType::Other),
+                    ItemState::Saved => ("saved".to_string(), theme::TextType::Other),
+                    ItemState::Failed(reason) => (reason.clone(), theme::TextType::Failed),
+                };
+                col.push(row![
+                    text(name).size(13).width(Length::FillPortion(50)),
+                    text(status)
+                        .size(13)
+                        .style(style)
+                        .width(Length::FillPortion(50)),
+                ])
+            });
+
+        container(column![header, scrollable(list)].spacing(6).padding(8))
+            .style(theme::ContainerType::Inner)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+
+    pub fn palette(&self) -> &Palette {
+        &self.config.palette
+    }
+
+    /// Adds the palette to the picker, replacing one with the same name, and selects it
+    pub fn add_palette(&mut self, palette: Palette) {
+        match self.palettes.iter_mut().find(|p| p.name == palette.name) {
+            Some(p) => *p = palette.clone(),
+            None => self.palettes.push(palette.clone()),
+        }
+        self.config.palette = palette;
+    }
+
+    pub fn report(&mut self, error: NordifyError) {
+        self.error = Some(error);
+    }
+
+    /// Error banner for the last failed job, if it hasn't been dismissed yet
+    pub fn banner(&self) -> Option<crate::IcedElement> {
+        let error = self.error.as_ref()?;
+        Some(
+            container(
+                row![
+                    text(error.to_string())
+                        .size(16)
+                        .width(Length::Fill)
+                        .vertical_alignment(iced::alignment::Vertical::Center),
+                    button(text("DISMISS").size(16))
+                        .on_press(Event::Menu(MenuEvent::DismissError))
+                        .style(theme::ButtonType::MainButton {
+                            btype: theme::MainType::Reset
+                        }),
+                ]
+                .spacing(10)
+                .align_items(iced::Alignment::Center),
+            )
+            .style(theme::ContainerType::Error)
+            .padding(6)
+            .width(Length::Fill)
+            .into(),
+        )
+    }
+
+    /// Shows a cached preview if there is one, and starts rendering it otherwise
+    fn preview(&mut self, previews: &mut Previews, input: &str, max: Option<(u32, u32)>) {
+        // Previews stay in memory, so there is no output path
+        let task = self.task(JobKind::Preview { max }, input, PathBuf::new());
+        self.job = None;
+        self.stage = None;
+
+        let key = CacheKey::new(&task.job, max);
+        match self.cache.get(&key) {
+            Some(rendered) => {
+                previews
+                    .nordified
+                    .show(task.id, &task.job.input, rendered.clone());
+                previews.palette = Some(task.job.palette.clone());
+                self.snapshot(&task.job, &rendered);
+            }
+            None => {
+                self.pending = Some((task.id, key));
+                self.job = Some(task);
+            }
+        }
+    }
+
+    /// A job with the current settings and a fresh id
+    fn task(
+        &mut self,
+        kind: JobKind,
+        input: impl Into<PathBuf>,
+        output: impl Into<PathBuf>,
+    ) -> Task {
+        self.next_id += 1;
+        Task {
+            id: self.next_id,
+            kind,
+            job: Job {
+                input: input.into(),
+                output: output.into(),
+                mode: self.config.mode,
+                kval: self.config.kval,
+                palette: self.config.palette.clone(),
+            },
+        }
+    }
+
+    fn job_update(&mut self, previews: &mut Previews, browser: &mut Browser, event: JobEvent) {
+        let preview = self.job.as_ref().map(|task| task.id);
+        let save = self.saves.front().map(|task| task.id);
+
+        match event {
+            JobEvent::Started(id) if Some(id) == preview => self.stage = None,
+            JobEvent::Started(id) if Some(id) == save => self.save_stage = None,
+            JobEvent::Progress(id, stage) if Some(id) == preview => self.stage = Some(stage),
+            JobEvent::Progress(id, stage) if Some(id) == save => self.save_stage = Some(stage),
+            JobEvent::Finished(id, _) if Some(id) == save => {
+                self.saves.pop_front();
+                self.save_stage = None;
+                browser.reload_contents();
+            }
+            JobEvent::Rendered(id, rendered) if Some(id) == preview => {
+                if let Some((_, key)) = self.pending.take().filter(|(p, _)| *p == id) {
+                    self.cache.insert(key, rendered.clone());
+                }
+                if let Some(task) = self.job.take() {
+                    self.snapshot(&task.job, &rendered);
+                    previews.nordified.show(id, &task.job.input, rendered);
+                    previews.palette = Some(task.job.palette);
+                }
+                self.stage = None;
+            }
+            JobEvent::Failed(id, e) if Some(id) == preview => {
+                self.job = None;
+                self.stage = None;
+                self.error = Some(e);
+            }
+            JobEvent::Failed(id, e) if Some(id) == save => {
+                self.saves.pop_front();
+                self.save_stage = None;
+                self.error = Some(e);
+            }
+            _ => (),
+        }
+    }
+
+    fn status(&self) -> crate::IcedElement {
+        let line = |task: &Task, stage: Option<Stage>, cancel: MenuEvent| {
+            let stage = stage
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| "Starting".into());
+            let kind = match task.kind {
+                JobKind::Preview { max: Some(_) } => "preview".to_string(),
+                JobKind::Preview { max: None } => "full resolution preview".to_string(),
+                JobKind::Save => match self.saves.len() {
+                    1 => "save".to_string(),
+                    n => format!("save, {} more queued", n - 1),
+                },
+            };
+            row![
+                container(
+                    text(format!("{stage} {kind}..."))
+                        .size(14)
+                        .style(theme::TextType::Label)
+                )
+                .width(Length::Fill)
+                .padding(3),
+                button(text("CANCEL").size(14))
+                    .on_press(Event::Menu(cancel))
+                    .style(theme::ButtonType::MainButton {
+                        btype: theme::MainType::Cancel
+                    }),
+            ]
+            .width(Length::Fill)
+        };
+
+        let col = column![].spacing(4);
+        let col = match &self.job {
+            Some(task) => col.push(line(task, self.stage, MenuEvent::CancelPreview)),
+            None => col,
+        };
+        let col = match self.saves.front() {
+            Some(task) => col.push(line(task, self.save_stage, MenuEvent::CancelSaves)),
+            None => col,
+        };
+        col.into()
+    }
+
     fn options(&self) -> crate::IcedElement {
-        if self.config.mode == Mode::Knn {
+        if let Some(run) = &self.batch {
+            self.batch_view(run)
+        } else if let Some(run) = &self.watch {
+            self.watch_view(run)
+        } else if self.config.mode == Mode::Knn {
             container(
                 container(
                     row![
@@ -272,10 +1211,19 @@ impl Menu {
     }
 }
 
+#[derive(Clone, PartialEq)]
 pub struct Config {
     mode: Mode,
     pub filename: String,
     kval: u8,
+    palette: Palette,
+}
+
+impl Config {
+    /// Equal apart from the filename
+    fn same_settings(&self, other: &Config) -> bool {
+        self.mode == other.mode && self.kval == other.kval && self.palette == other.palette
+    }
 }
 
 impl Default for Config {
@@ -284,14 +1232,7 @@ impl Default for Config {
             mode: Default::default(),
             filename: Default::default(),
             kval: 32,
+            palette: Default::default(),
         }
     }
 }
-
-fn nordify<M: Mapper>(opts: ProcOptions<M>, file: &Path, out: &Path) {
-    opts.load(file)
-        .expect("failed to load file")
-        .process()
-        .save(out)
-        .expect("failed to save")
-}
@@ -0,0 +1,130 @@
+use std::{fs, io, path::PathBuf};
+
+pub mod extract;
+pub mod import;
+mod scheme;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Palette {
+    pub name: String,
+    pub colors: Vec<[u8; 3]>,
+}
+
+impl Palette {
+    pub fn new(name: impl Into<String>, colors: Vec<[u8; 3]>) -> Self {
+        Palette {
+            name: name.into(),
+            colors,
+        }
+    }
+
+    fn from_hex(name: &str, hex: &[u32]) -> Self {
+        Palette::new(
+            name,
+            hex.iter()
+                .map(|c| [(c >> 16) as u8, (c >> 8) as u8, *c as u8])
+                .collect(),
+        )
+    }
+
+    /// Palettes saved from the editor, stored as GIMP palettes in the config directory
+    pub fn user() -> Vec<Palette> {
+        let mut palettes: Vec<Palette> = user_dir()
+            .and_then(|dir| fs::read_dir(dir).ok())
+            .map(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .filter_map(|e| import::import(e.path()).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        palettes.sort_by(|a, b| a.name.cmp(&b.name));
+        palettes
+    }
+
+    pub fn save(&self) -> io::Result<PathBuf> {
+        if self.name.trim().is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the palette needs a name",
+            ));
+        }
+        let mut path = user_dir()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+        fs::create_dir_all(&path)?;
+        let filename: String = self
+            .name
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        path.push(format!("{filename}.gpl"));
+        fs::write(&path, self.to_gpl())?;
+        Ok(path)
+    }
+
+    pub fn to_gpl(&self) -> String {
+        let mut gpl = format!("GIMP Palette\nName: {}\nColumns: 8\n#\n", self.name);
+        for [r, g, b] in &self.colors {
+            gpl.push_str(&format!("{r:3} {g:3} {b:3}\t#{r:02x}{g:02x}{b:02x}\n"));
+        }
+        gpl
+    }
+
+    /// Builtin palettes followed by the user's, which replace builtins of the same name
+    pub fn available() -> Vec<Palette> {
+        let user = Palette::user();
+        let mut palettes: Vec<Palette> = Palette::builtin()
+            .into_iter()
+            .filter(|b| !user.iter().any(|u| u.name == b.name))
+            .collect();
+        palettes.extend(user);
+        palettes
+    }
+
+    pub fn builtin() -> Vec<Palette> {
+        vec![
+            Palette::from_hex("Nord", &NORD),
+            Palette::from_hex("Gruvbox", &GRUVBOX),
+            Palette::from_hex("Catppuccin", &CATPPUCCIN),
+            Palette::from_hex("Dracula", &DRACULA),
+        ]
+    }
+}
+
+pub fn user_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("nordify").join("palettes"))
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::from_hex("Nord", &NORD)
+    }
+}
+
+impl std::fmt::Display for Palette {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+static NORD: [u32; 16] = [
+    0x2e3440, 0x3b4252, 0x434c5e, 0x4c566a, 0xd8dee9, 0xe5e9f0, 0xeceff4, 0x8fbcbb, 0x88c0d0,
+    0x81a1c1, 0x5e81ac, 0xbf616a, 0xd08770, 0xebcb8b, 0xa3be8c, 0xb48ead,
+];
+
+static GRUVBOX: [u32; 23] = [
+    0x282828, 0xcc241d, 0x98971a, 0xd79921, 0x458588, 0xb16286, 0x689d6a, 0xa89984, 0x928374,
+    0xfb4934, 0xb8bb26, 0xfabd2f, 0x83a598, 0xd3869b, 0x8ec07c, 0xebdbb2, 0x1d2021, 0x3c3836,
+    0x504945, 0x665c54, 0x7c6f64, 0xfe8019, 0xd65d0e,
+];
+
+static CATPPUCCIN: [u32; 26] = [
+    0xf5e0dc, 0xf2cdcd, 0xf5c2e7, 0xcba6f7, 0xf38ba8, 0xeba0ac, 0xfab387, 0xf9e2af, 0xa6e3a1,
+    0x94e2d5, 0x89dceb, 0x74c7ec, 0x89b4fa, 0xb4befe, 0xcdd6f4, 0xbac2de, 0xa6adc8, 0x9399b2,
+    0x7f849c, 0x6c7086, 0x585b70, 0x45475a, 0x313244, 0x1e1e2e, 0x181825, 0x11111b,
+];
+
+static DRACULA: [u32; 11] = [
+    0x282a36, 0x44475a, 0xf8f8f2, 0x6272a4, 0x8be9fd, 0x50fa7b, 0xffb86c, 0xff79c6, 0xbd93f9,
+    0xff5555, 0xf1fa8c,
+];
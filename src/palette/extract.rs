@@ -0,0 +1,120 @@
+//! Palette extraction from a reference image: median cut picks the starting colors,
+//! which are then refined with a few rounds of k-means
+
+use std::path::Path;
+
+const SAMPLE_SIZE: u32 = 256;
+const ROUNDS: usize = 10;
+
+pub fn extract<P: AsRef<Path>>(path: P, n: usize) -> image::ImageResult<Vec<[u8; 3]>> {
+    let img = image::open(path)?
+        .thumbnail(SAMPLE_SIZE, SAMPLE_SIZE)
+        .to_rgb8();
+    let pixels: Vec<[u8; 3]> = img.pixels().map(|p| p.0).collect();
+    Ok(quantize(&pixels, n))
+}
+
+pub fn quantize(pixels: &[[u8; 3]], n: usize) -> Vec<[u8; 3]> {
+    if pixels.is_empty() || n == 0 {
+        return Vec::new();
+    }
+    let seeds = median_cut(pixels.to_vec(), n);
+    let mut colors = kmeans(pixels, seeds);
+    colors.sort_by_key(|c| luma(*c));
+    colors.dedup();
+    colors
+}
+
+fn median_cut(pixels: Vec<[u8; 3]>, n: usize) -> Vec<[u8; 3]> {
+    let mut boxes = vec![pixels];
+
+    while boxes.len() < n {
+        // Split the box with the widest channel range
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.len() > 1)
+            .map(|(i, b)| (i, widest_channel(b)))
+            .max_by_key(|(_, (_, range))| *range);
+
+        let (i, channel) = match widest {
+            Some((i, (channel, range))) if range > 0 => (i, channel),
+            _ => break,
+        };
+
+        let mut b = boxes.swap_remove(i);
+        b.sort_unstable_by_key(|p| p[channel]);
+        let upper = b.split_off(b.len() / 2);
+        boxes.push(b);
+        boxes.push(upper);
+    }
+
+    boxes.iter().map(|b| mean(b.iter())).collect()
+}
+
+fn widest_channel(pixels: &[[u8; 3]]) -> (usize, u8) {
+    (0..3)
+        .map(|c| {
+            let (min, max) = pixels.iter().fold((u8::MAX, u8::MIN), |(lo, hi), p| {
+                (lo.min(p[c]), hi.max(p[c]))
+            });
+            (c, max - min)
+        })
+        .max_by_key(|(_, range)| *range)
+        .unwrap_or((0, 0))
+}
+
+fn kmeans(pixels: &[[u8; 3]], mut centers: Vec<[u8; 3]>) -> Vec<[u8; 3]> {
+    for _ in 0..ROUNDS {
+        let mut clusters = vec![Vec::new(); centers.len()];
+        for p in pixels {
+            clusters[nearest(&centers, *p)].push(p);
+        }
+
+        let next: Vec<[u8; 3]> = clusters
+            .iter()
+            .zip(&centers)
+            .map(|(cluster, center)| {
+                if cluster.is_empty() {
+                    *center
+                } else {
+                    mean(cluster.iter().copied())
+                }
+            })
+            .collect();
+
+        if next == centers {
+            break;
+        }
+        centers = next;
+    }
+    centers
+}
+
+pub fn nearest(colors: &[[u8; 3]], p: [u8; 3]) -> usize {
+    colors
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, c)| distance(**c, p))
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    a.iter()
+        .zip(&b)
+        .map(|(x, y)| (*x as i32 - *y as i32).pow(2) as u32)
+        .sum()
+}
+
+fn mean<'a>(pixels: impl Iterator<Item = &'a [u8; 3]>) -> [u8; 3] {
+    let (sum, count) = pixels.fold(([0u64; 3], 0u64), |(mut sum, count), p| {
+        sum.iter_mut().zip(p).for_each(|(s, c)| *s += *c as u64);
+        (sum, count + 1)
+    });
+    sum.map(|s| (s / count.max(1)) as u8)
+}
+
+fn luma([r, g, b]: [u8; 3]) -> u32 {
+    299 * r as u32 + 587 * g as u32 + 114 * b as u32
+}
@@ -0,0 +1,432 @@
+use std::{fs, path::Path};
+
+use whatsinaname::AboutFile;
+
+use super::{scheme, Palette};
+
+pub static EXT: [&str; 11] = [
+    "gpl",
+    "ase",
+    "json",
+    "txt",
+    "hex",
+    "yaml",
+    "yml",
+    "toml",
+    "conf",
+    "xresources",
+    "xdefaults",
+];
+
+/// Extensions nothing but palettes use
+static PALETTE_EXT: [&str; 3] = ["gpl", "ase", "hex"];
+
+/// Larger files aren't read to find out whether they are palettes
+const SNIFF_LIMIT: u64 = 64 * 1024;
+
+/// Text formats that other files share, like JSON, TOML or plain text, only count when
+/// they parse as a palette. Xresources files usually come without an extension.
+pub fn is_palette_file(path: &Path) -> bool {
+    let lower = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_lowercase();
+    if lower.has_extension(&PALETTE_EXT) {
+        return true;
+    }
+    let candidate =
+        lower.has_extension(&EXT) || lower.ends_with("xresources") || lower.ends_with("xdefaults");
+    candidate && fs::metadata(path).is_ok_and(|m| m.len() <= SNIFF_LIMIT) && import(path).is_ok()
+}
+
+#[derive(Clone, Debug)]
+pub enum ImportError {
+    Read(String),
+    Format(String),
+    Empty,
+}
+
+impl std::fmt::Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Read(reason) => write!(f, "{reason}"),
+            Self::Format(reason) => write!(f, "invalid palette file ({reason})"),
+            Self::Empty => write!(f, "no colors found"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+pub type ImportResult = Result<Palette, ImportError>;
+
+/// Picks a parser based on the file extension
+pub fn import<P: AsRef<Path>>(path: P) -> ImportResult {
+    let path = path.as_ref();
+    let bytes = fs::read(path).map_err(|e| ImportError::Read(e.to_string()))?;
+    let filename = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let name = filename.get_name();
+    let ext = path
+        .extension()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_lowercase();
+
+    match ext.as_str() {
+        "gpl" => gpl(text(&bytes)?, name),
+        "ase" => ase(&bytes, name),
+        "json" => json(text(&bytes)?, name),
+        "yaml" | "yml" => scheme::yaml(text(&bytes)?, name),
+        "toml" => scheme::toml(text(&bytes)?, name),
+        "conf" => scheme::kitty(text(&bytes)?, name),
+        "txt" | "hex" => hex_list(text(&bytes)?, name),
+        _ if filename.to_lowercase().contains("xresources")
+            || filename.to_lowercase().contains("xdefaults") =>
+        {
+            scheme::xresources(text(&bytes)?, name)
+        }
+        _ => hex_list(text(&bytes)?, name),
+    }
+}
+
+fn text(bytes: &[u8]) -> Result<&str, ImportError> {
+    std::str::from_utf8(bytes).map_err(|_| ImportError::Format("not valid UTF-8 text".into()))
+}
+
+/// Validates and deduplicates the colors, keeping the order they were listed in
+pub(super) fn finish(name: &str, colors: Vec<[u8; 3]>) -> ImportResult {
+    let mut unique = Vec::with_capacity(colors.len());
+    for c in colors {
+        if !unique.contains(&c) {
+            unique.push(c);
+        }
+    }
+    if unique.is_empty() {
+        return Err(ImportError::Empty);
+    }
+    Ok(Palette::new(name, unique))
+}
+
+/// Parses `#rrggbb`, `rrggbb` or `#rgb`
+pub fn parse_hex(s: &str) -> Option<[u8; 3]> {
+    let s = s.trim().trim_start_matches('#');
+    if !s.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    match s.len() {
+        6 => {
+            let v = u32::from_str_radix(s, 16).ok()?;
+            Some([(v >> 16) as u8, (v >> 8) as u8, v as u8])
+        }
+        3 => {
+            let v = u16::from_str_radix(s, 16).ok()?;
+            let expand = |n: u16| (n & 0xf) as u8 * 0x11;
+            Some([expand(v >> 8), expand(v >> 4), expand(v)])
+        }
+        _ => None,
+    }
+}
+
+/// GIMP palette: a `GIMP Palette` header, optional `Name:`/`Columns:` lines,
+/// `#` comments and `R G B [name]` rows
+fn gpl(src: &str, name: &str) -> ImportResult {
+    let mut lines = src.lines();
+    if lines.next().map(str::trim) != Some("GIMP Palette") {
+        return Err(ImportError::Format("missing GIMP Palette header".into()));
+    }
+
+    let mut name = name.to_string();
+    let mut colors = Vec::new();
+    for (n, line) in lines.enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("Columns:") {
+            continue;
+        }
+        if let Some(n) = line.strip_prefix("Name:") {
+            name = n.trim().to_string();
+            continue;
+        }
+        let rgb: Vec<u8> = line
+            .split_whitespace()
+            .take(3)
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map_err(|_| ImportError::Format(format!("bad color on line {}", n + 2)))?;
+        match rgb[..] {
+            [r, g, b] => colors.push([r, g, b]),
+            _ => return Err(ImportError::Format(format!("bad color on line {}", n + 2))),
+        }
+    }
+
+    finish(&name, colors)
+}
+
+/// JSON array of hex strings, e.g. `["#2e3440", "#88c0d0"]`
+fn json(src: &str, name: &str) -> ImportResult {
+    let list: Vec<String> =
+        serde_json::from_str(src).map_err(|e| ImportError::Format(e.to_string()))?;
+    let colors = list
+        .iter()
+        .map(|s| parse_hex(s).ok_or_else(|| ImportError::Format(format!("bad color {s:?}"))))
+        .collect::<Result<_, _>>()?;
+    finish(name, colors)
+}
+
+/// One hex color per line; blank lines and lines starting with `//` or `;` are skipped
+fn hex_list(src: &str, name: &str) -> ImportResult {
+    let colors = src
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with("//") && !l.starts_with(';'))
+        .map(|l| parse_hex(l).ok_or_else(|| ImportError::Format(format!("bad color {l:?}"))))
+        .collect::<Result<_, _>>()?;
+    finish(name, colors)
+}
+
+/// Adobe Swatch Exchange: big-endian binary with a `ASEF` signature, followed by
+/// color entry and group blocks. Only color entries are kept, groups are flattened.
+fn ase(bytes: &[u8], name: &str) -> ImportResult {
+    let mut r = Reader { bytes, pos: 0 };
+    if r.take(4)? != b"ASEF" {
+        return Err(ImportError::Format("missing ASEF signature".into()));
+    }
+    r.take(4)?; // version
+    let blocks = r.u32()?;
+
+    let mut colors = Vec::new();
+    for _ in 0..blocks {
+        let kind = r.u16()?;
+        let len = r.u32()? as usize;
+        let block = r.take(len)?;
+        if kind == 0x0001 {
+            colors.push(ase_color(block)?);
+        }
+    }
+
+    finish(name, colors)
+}
+
+fn ase_color(block: &[u8]) -> Result<[u8; 3], ImportError> {
+    let mut r = Reader {
+        bytes: block,
+        pos: 0,
+    };
+    let name_len = r.u16()? as usize;
+    r.take(name_len * 2)?;
+    let model = r.take(4)?;
+
+    let to_u8 = |v: f32| (v.clamp(0., 1.) * 255.).round() as u8;
+    match model {
+        b"RGB " => Ok([to_u8(r.f32()?), to_u8(r.f32()?), to_u8(r.f32()?)]),
+        b"CMYK" => {
+            let (c, m, y, k) = (r.f32()?, r.f32()?, r.f32()?, r.f32()?);
+            Ok([
+                to_u8((1. - c) * (1. - k)),
+                to_u8((1. - m) * (1. - k)),
+                to_u8((1. - y) * (1. - k)),
+            ])
+        }
+        b"Gray" => {
+            let v = to_u8(r.f32()?);
+            Ok([v, v, v])
+        }
+        b"LAB " => Ok(lab_to_rgb(r.f32()? * 100., r.f32()?, r.f32()?)),
+        _ => Err(ImportError::Format("unknown color model".into())),
+    }
+}
+
+/// CIE L*a*b* (D65) to sRGB
+pub fn lab_to_rgb(l: f32, a: f32, b: f32) -> [u8; 3] {
+    let fy = (l + 16.) / 116.;
+    let fx = fy + a / 500.;
+    let fz = fy - b / 200.;
+    let inv = |t: f32| {
+        if t > 6. / 29. {
+            t.powi(3)
+        } else {
+            3. * (6f32 / 29.).powi(2) * (t - 4. / 29.)
+        }
+    };
+    let (x, y, z) = (0.95047 * inv(fx), inv(fy), 1.08883 * inv(fz));
+
+    let gamma = |c: f32| {
+        let c = if c <= 0.0031308 {
+            12.92 * c
+        } else {
+            1.055 * c.powf(1. / 2.4) - 0.055
+        };
+        (c.clamp(0., 1.) * 255.).round() as u8
+    };
+    [
+        gamma(3.2406 * x - 1.5372 * y - 0.4986 * z),
+        gamma(-0.9689 * x + 1.8758 * y + 0.0415 * z),
+        gamma(0.0557 * x - 0.2040 * y + 1.0570 * z),
+    ]
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], ImportError> {
+        let end = self.pos + n;
+        let slice = self
+            .bytes
+            .get(self.pos..end)
+            .ok_or_else(|| ImportError::Format("unexpected end of file".into()))?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u16(&mut self) -> Result<u16, ImportError> {
+        let b = self.take(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, ImportError> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn f32(&mut self) -> Result<f32, ImportError> {
+        Ok(f32::from_bits(self.u32()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_hex_forms() {
+        assert_eq!(parse_hex("#2e3440"), Some([0x2e, 0x34, 0x40]));
+        assert_eq!(parse_hex(" 88C0D0 "), Some([0x88, 0xc0, 0xd0]));
+        assert_eq!(parse_hex("#abc"), Some([0xaa, 0xbb, 0xcc]));
+        assert_eq!(parse_hex("#12345"), None);
+        assert_eq!(parse_hex("#ggg"), None);
+        assert_eq!(parse_hex("+12345"), None);
+        assert_eq!(parse_hex(""), None);
+    }
+
+    #[test]
+    fn gpl_reads_name_and_rows() {
+        let src = "GIMP Palette\nName: Nord\nColumns: 8\n#\n 46  52  64\tnord0\n136 192 208\n";
+        let palette = gpl(src, "file").unwrap();
+        assert_eq!(palette.name, "Nord");
+        assert_eq!(palette.colors, [[46, 52, 64], [136, 192, 208]]);
+    }
+
+    #[test]
+    fn gpl_rejects_malformed_files() {
+        assert!(matches!(
+            gpl("46 52 64\n", "file"),
+            Err(ImportError::Format(_))
+        ));
+        assert!(matches!(
+            gpl("GIMP Palette\n46 52\n", "file"),
+            Err(ImportError::Format(_))
+        ));
+        assert!(matches!(
+            gpl("GIMP Palette\n46 52 256\n", "file"),
+            Err(ImportError::Format(_))
+        ));
+        assert!(matches!(
+            gpl("GIMP Palette\nName: Empty\n", "file"),
+            Err(ImportError::Empty)
+        ));
+    }
+
+    #[test]
+    fn json_list_of_hex_strings() {
+        let palette = json(r##"["#2e3440", "#88c0d0", "#2e3440"]"##, "file").unwrap();
+        assert_eq!(palette.name, "file");
+        assert_eq!(palette.colors, [[0x2e, 0x34, 0x40], [0x88, 0xc0, 0xd0]]);
+
+        assert!(json(r#"{"colors": []}"#, "file").is_err());
+        assert!(json(r##"["#2e3440", "nord"]"##, "file").is_err());
+        assert!(matches!(json("[]", "file"), Err(ImportError::Empty)));
+    }
+
+    #[test]
+    fn hex_list_skips_comments() {
+        let palette = hex_list("// Nord\n#2e3440\n\n; frost\n88c0d0\n", "file").unwrap();
+        assert_eq!(palette.colors, [[0x2e, 0x34, 0x40], [0x88, 0xc0, 0xd0]]);
+        assert!(hex_list("#2e3440\nnot a color\n", "file").is_err());
+    }
+
+    /// An ASE file with one block per `(model, values)`
+    fn ase_file(entries: &[(&[u8; 4], &[f32])]) -> Vec<u8> {
+        let mut bytes = b"ASEF\x00\x01\x00\x00".to_vec();
+        bytes.extend((entries.len() as u32).to_be_bytes());
+        for (model, values) in entries {
+            let mut block = 2u16.to_be_bytes().to_vec();
+            block.extend([0, b'a', 0, 0]);
+            block.extend(model.iter());
+            for v in values.iter() {
+                block.extend(v.to_be_bytes());
+            }
+            block.extend(2u16.to_be_bytes());
+
+            bytes.extend(1u16.to_be_bytes());
+            bytes.extend((block.len() as u32).to_be_bytes());
+            bytes.extend(block);
+        }
+        bytes
+    }
+
+    #[test]
+    fn ase_color_models() {
+        let bytes = ase_file(&[
+            (b"RGB ", &[1., 0., 0.5]),
+            (b"Gray", &[0.]),
+            (b"CMYK", &[0., 0., 0., 1.]),
+        ]);
+        let palette = ase(&bytes, "file").unwrap();
+        assert_eq!(palette.colors, [[255, 0, 128], [0, 0, 0]]);
+    }
+
+    #[test]
+    fn ase_rejects_malformed_files() {
+        assert!(ase(b"GIMP", "file").is_err());
+
+        let bytes = ase_file(&[(b"RGB ", &[1., 0., 0.5])]);
+        assert!(ase(&bytes[..bytes.len() - 4], "file").is_err());
+
+        let bytes = ase_file(&[(b"HSV ", &[1., 0., 0.5])]);
+        assert!(ase(&bytes, "file").is_err());
+    }
+
+    #[test]
+    fn only_parseable_text_files_are_palettes() {
+        let dir = std::env::temp_dir().join(format!("nordify-sniff-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let files = [
+            ("Cargo.toml", "[package]\nname = \"nordify\"\n"),
+            ("README.txt", "Nordify your wallpapers\n"),
+            ("package.json", r#"{"name": "nordify"}"#),
+            ("colors.txt", "#2e3440\n#88c0d0\n"),
+            ("colors.json", r##"["#2e3440"]"##),
+            ("broken.gpl", "not really a palette"),
+        ];
+        for (name, contents) in files {
+            fs::write(dir.join(name), contents).unwrap();
+        }
+
+        let palettes: Vec<&str> = files
+            .iter()
+            .map(|(name, _)| *name)
+            .filter(|name| is_palette_file(&dir.join(name)))
+            .collect();
+        fs::remove_dir_all(&dir).unwrap();
+
+        // Palette-only extensions are trusted, so the broken one shows up to report its error
+        assert_eq!(palettes, ["colors.txt", "colors.json", "broken.gpl"]);
+    }
+}
@@ -0,0 +1,214 @@
+//! Loaders for color schemes that come from terminals and editors rather than
+//! from image editors: base16/base24 YAML, Alacritty (TOML/YAML), Kitty and Xresources
+
+use serde_json::Value;
+use std::collections::HashMap;
+
+use super::import::{finish, parse_hex, ImportError, ImportResult};
+
+static ANSI: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// YAML files are either base16/base24 schemes or Alacritty configs
+pub fn yaml(src: &str, name: &str) -> ImportResult {
+    let value: Value = serde_yaml::from_str(src).map_err(|e| ImportError::Format(e.to_string()))?;
+    if value.get("colors").is_some() {
+        alacritty(&value, name)
+    } else {
+        base16(&value, name)
+    }
+}
+
+pub fn toml(src: &str, name: &str) -> ImportResult {
+    let value: Value = toml::from_str(src).map_err(|e| ImportError::Format(e.to_string()))?;
+    alacritty(&value, name)
+}
+
+/// `base00`..`base0F` (and `base10`..`base17` for base24), either at the top level
+/// or nested under `palette` as in the newer tinted-theming layout
+fn base16(value: &Value, name: &str) -> ImportResult {
+    let colors = value.get("palette").unwrap_or(value);
+    let name = ["scheme", "name"]
+        .iter()
+        .find_map(|k| value.get(k).and_then(Value::as_str))
+        .unwrap_or(name);
+
+    let colors = (0..0x18)
+        .map_while(|i| colors.get(format!("base{i:02X}")))
+        .map(|v| {
+            v.as_str()
+                .and_then(parse_hex)
+                .ok_or_else(|| ImportError::Format(format!("bad color {v}")))
+        })
+        .collect::<Result<_, _>>()?;
+
+    finish(name, colors)
+}
+
+fn alacritty(value: &Value, name: &str) -> ImportResult {
+    let colors = value
+        .get("colors")
+        .ok_or_else(|| ImportError::Format("missing colors section".into()))?;
+
+    let primary = ["background", "foreground"]
+        .iter()
+        .filter_map(|k| colors.get("primary").and_then(|p| p.get(k)));
+    let ansi = ["normal", "bright"].iter().flat_map(|section| {
+        ANSI.iter()
+            .filter_map(move |k| colors.get(section).and_then(|s| s.get(k)))
+    });
+
+    let colors = primary
+        .chain(ansi)
+        .map(|v| {
+            v.as_str()
+                .map(|s| s.trim_start_matches("0x"))
+                .and_then(parse_hex)
+                .ok_or_else(|| ImportError::Format(format!("bad color {v}")))
+        })
+        .collect::<Result<_, _>>()?;
+
+    finish(name, colors)
+}
+
+/// Kitty themes are `key value` lines, e.g. `color1 #bf616a`
+pub fn kitty(src: &str, name: &str) -> ImportResult {
+    let entries: HashMap<&str, &str> = src
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|l| l.split_once(char::is_whitespace))
+        .map(|(k, v)| (k, v.trim()))
+        .collect();
+
+    terminal(&entries, name)
+}
+
+/// Xresources lines look like `*.color1: #bf616a` or `URxvt*foreground: nord4`,
+/// where values may refer to `#define`d names
+pub fn xresources(src: &str, name: &str) -> ImportResult {
+    let mut defines = HashMap::new();
+    let mut entries = HashMap::new();
+
+    for line in src.lines().map(str::trim) {
+        if let Some(def) = line.strip_prefix("#define") {
+            if let Some((k, v)) = def.trim().split_once(char::is_whitespace) {
+                defines.insert(k, v.trim());
+            }
+        } else if !line.is_empty() && !line.starts_with('!') {
+            if let Some((k, v)) = line.split_once(':') {
+                let key = k.rsplit(['.', '*']).next().unwrap_or(k).trim();
+                entries.insert(key, v.trim());
+            }
+        }
+    }
+
+    for v in entries.values_mut() {
+        if let Some(d) = defines.get(*v) {
+            *v = *d;
+        }
+    }
+
+    terminal(&entries, name)
+}
+
+/// Background and foreground first, followed by the 16 ANSI colors
+fn terminal(entries: &HashMap<&str, &str>, name: &str) -> ImportResult {
+    let keys = ["background".to_string(), "foreground".to_string()]
+        .into_iter()
+        .chain((0..16).map(|i| format!("color{i}")));
+
+    let colors = keys
+        .filter_map(|k| entries.get(k.as_str()))
+        .map(|v| parse_hex(v).ok_or_else(|| ImportError::Format(format!("bad color {v:?}"))))
+        .collect::<Result<_, _>>()?;
+
+    finish(name, colors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NORD0: [u8; 3] = [0x2e, 0x34, 0x40];
+    const NORD4: [u8; 3] = [0xd8, 0xde, 0xe9];
+    const NORD11: [u8; 3] = [0xbf, 0x61, 0x6a];
+
+    #[test]
+    fn base16_at_the_top_level() {
+        let src = "scheme: \"Nord\"\nbase00: \"2e3440\"\nbase01: \"d8dee9\"\nbase02: \"bf616a\"\n";
+        let palette = yaml(src, "file").unwrap();
+        assert_eq!(palette.name, "Nord");
+        assert_eq!(palette.colors, [NORD0, NORD4, NORD11]);
+    }
+
+    #[test]
+    fn base24_nested_under_palette() {
+        let src = "name: Short\npalette:\n  base00: \"#2e3440\"\n  base01: \"#fff\"\n";
+        let palette = yaml(src, "file").unwrap();
+        assert_eq!(palette.name, "Short");
+        assert_eq!(palette.colors, [NORD0, [255, 255, 255]]);
+    }
+
+    #[test]
+    fn base16_stops_at_the_first_missing_key() {
+        let src = "base00: \"2e3440\"\nbase02: \"bf616a\"\n";
+        assert_eq!(yaml(src, "file").unwrap().colors, [NORD0]);
+        assert!(matches!(
+            yaml("scheme: Nord\n", "file"),
+            Err(ImportError::Empty)
+        ));
+        assert!(matches!(
+            yaml("base00: \"nord\"\n", "file"),
+            Err(ImportError::Format(_))
+        ));
+    }
+
+    #[test]
+    fn alacritty_yaml() {
+        let src = "colors:\n  primary:\n    background: '0x2e3440'\n    foreground: '#d8dee9'\n  \
+                   normal:\n    red: '#bf616a'\n";
+        let palette = yaml(src, "nord").unwrap();
+        assert_eq!(palette.name, "nord");
+        assert_eq!(palette.colors, [NORD0, NORD4, NORD11]);
+    }
+
+    #[test]
+    fn alacritty_toml() {
+        let src =
+            "[colors.primary]\nbackground = \"#2e3440\"\n\n[colors.bright]\nred = \"#bf616a\"\n";
+        assert_eq!(toml(src, "nord").unwrap().colors, [NORD0, NORD11]);
+        assert!(matches!(
+            toml("[window]\nopacity = 0.9\n", "nord"),
+            Err(ImportError::Format(_))
+        ));
+        assert!(toml("[colors.primary]\nbackground = \"#2e34\"\n", "nord").is_err());
+    }
+
+    #[test]
+    fn kitty_orders_background_foreground_then_ansi() {
+        let src = "# Nord\ncolor1 #bf616a\nforeground #d8dee9\nbackground   #2e3440\ncursor #fff\n";
+        assert_eq!(kitty(src, "nord").unwrap().colors, [NORD0, NORD4, NORD11]);
+        assert!(kitty("background nord0\n", "nord").is_err());
+        assert!(matches!(
+            kitty("font_size 12\n", "nord"),
+            Err(ImportError::Empty)
+        ));
+    }
+
+    #[test]
+    fn xresources_resolves_defines() {
+        let src = "! Nord\n#define nord0 #2e3440\n*.background: nord0\n\
+                   URxvt*foreground: #d8dee9\n*color1: #bf616a\n";
+        assert_eq!(
+            xresources(src, "nord").unwrap().colors,
+            [NORD0, NORD4, NORD11]
+        );
+        assert!(xresources("*.background: #fff\n", "nord").unwrap().colors == [[255; 3]]);
+        assert!(matches!(
+            xresources("! nothing here\n", "nord"),
+            Err(ImportError::Empty)
+        ));
+    }
+}
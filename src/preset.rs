@@ -0,0 +1,156 @@
+//! Named combinations of mode, K and palette, kept in `presets.toml` in the config directory
+
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::PathBuf};
+
+use crate::{
+    palette::{import::parse_hex, Palette},
+    Mode,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Preset {
+    pub name: String,
+    pub mode: Mode,
+    pub kval: u8,
+    /// Stored with its colours, so it still works if the palette file goes away
+    pub palette: Palette,
+}
+
+/// Layout of `presets.toml`
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PresetFile {
+    #[serde(default)]
+    preset: Vec<PresetEntry>,
+}
+
+/// A [`Preset`] as written to the file, with its colours in hex
+#[derive(Debug, Serialize, Deserialize)]
+struct PresetEntry {
+    name: String,
+    mode: String,
+    kval: u8,
+    palette: String,
+    colors: Vec<String>,
+}
+
+pub fn path() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("nordify").join("presets.toml"))
+}
+
+/// Saved presets in file order. Presets with an unknown mode or colour are skipped.
+pub fn load() -> Vec<Preset> {
+    path()
+        .and_then(|p| fs::read_to_string(p).ok())
+        .map(|src| from_toml(&src))
+        .unwrap_or_default()
+}
+
+/// Replaces the file with `presets`
+pub fn save(presets: &[Preset]) -> io::Result<PathBuf> {
+    let path =
+        path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let src = to_toml(presets).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    fs::write(&path, src)?;
+    Ok(path)
+}
+
+fn to_toml(presets: &[Preset]) -> Result<String, toml::ser::Error> {
+    toml::to_string(&PresetFile {
+        preset: presets.iter().map(to_entry).collect(),
+    })
+}
+
+fn from_toml(src: &str) -> Vec<Preset> {
+    toml::from_str::<PresetFile>(src)
+        .map(|file| file.preset.iter().filter_map(from_entry).collect())
+        .unwrap_or_default()
+}
+
+fn to_entry(preset: &Preset) -> PresetEntry {
+    PresetEntry {
+        name: preset.name.clone(),
+        mode: preset.mode.to_string(),
+        kval: preset.kval,
+        palette: preset.palette.name.clone(),
+        colors: preset
+            .palette
+            .colors
+            .iter()
+            .map(|[r, g, b]| format!("#{r:02x}{g:02x}{b:02x}"))
+            .collect(),
+    }
+}
+
+fn from_entry(entry: &PresetEntry) -> Option<Preset> {
+    let colors = entry
+        .colors
+        .iter()
+        .map(|c| parse_hex(c))
+        .collect::<Option<Vec<_>>>()?;
+    Some(Preset {
+        name: entry.name.clone(),
+        mode: entry.mode.parse().ok()?,
+        kval: entry.kval,
+        palette: Palette::new(&entry.palette, colors),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_survive_saving_and_loading() {
+        let presets = vec![
+            Preset {
+                name: "Soft".into(),
+                mode: Mode::Knn,
+                kval: 12,
+                palette: Palette::new("Nord", vec![[0x2e, 0x34, 0x40], [0xd8, 0xde, 0xe9]]),
+            },
+            Preset {
+                name: "Loud".into(),
+                mode: Mode::Creative,
+                kval: 0,
+                palette: Palette::new("Mono", vec![[0, 0, 0], [255, 255, 255]]),
+            },
+        ];
+
+        let src = to_toml(&presets).unwrap();
+        assert_eq!(from_toml(&src), presets);
+    }
+
+    #[test]
+    fn unreadable_presets_are_skipped() {
+        let src = r##"
+[[preset]]
+name = "Odd"
+mode = "Sideways"
+kval = 4
+palette = "Nord"
+colors = ["#2e3440"]
+
+[[preset]]
+name = "Broken"
+mode = "Default"
+kval = 4
+palette = "Nord"
+colors = ["nord0"]
+
+[[preset]]
+name = "Fine"
+mode = "kNN"
+kval = 4
+palette = "Nord"
+colors = ["#2e3440"]
+"##;
+        let presets = from_toml(src);
+        assert_eq!(presets.len(), 1);
+        assert_eq!(presets[0].name, "Fine");
+        assert!(from_toml("").is_empty());
+    }
+}
@@ -1,28 +1,58 @@
-use iced::widget::{image, image::Handle, row, tooltip};
-use iced::{ContentFit, Length};
+use iced::widget::{button, column, container, image::Handle, row, text, tooltip};
+use iced::{Color, ContentFit, Length, Subscription};
+use image::RgbaImage;
+use nordify_gui::palette::{extract, Palette};
+use std::path::{Path, PathBuf};
 
-use crate::theme;
+use crate::process::{self, Compared, Decoded, Rendered};
+use crate::zoom::{zoom_image, Zoom, ZoomImage};
+use crate::{theme, Event};
 
 static NORD_SRC: &[u8] = include_bytes!("../media/preview2.png");
 static ORIG_SRC: &[u8] = include_bytes!("../media/preview1.png");
 
+/// Pane sizes are rounded up to this many pixels when sizing quick previews
+const PANE_STEP: u32 = 256;
+
+/// The latest preview, kept in memory
 #[derive(Default)]
 pub struct NordifiedImage {
-    loc: String,
+    /// Id of the job that rendered it, and the image it was made from
+    render: Option<(u64, PathBuf, Rendered)>,
 }
 
-impl ImageView for NordifiedImage {
-    fn src(&self) -> &[u8] {
-        NORD_SRC
+impl NordifiedImage {
+    pub fn show(&mut self, id: u64, input: &Path, rendered: Rendered) {
+        self.render = Some((id, input.to_path_buf(), rendered));
     }
 
-    fn location(&self) -> &str {
-        &self.loc
+    fn id(&self) -> Option<u64> {
+        self.render.as_ref().map(|(id, ..)| *id)
     }
 
-    fn set_loc(&mut self, new: &str) {
-        self.loc.clear();
-        self.loc.push_str(new);
+    /// The preview, if it was made from the image at `loc`
+    fn of(&self, loc: &str) -> Option<(u64, &Rendered)> {
+        match &self.render {
+            Some((id, input, rendered)) if !loc.is_empty() && input == Path::new(loc) => {
+                Some((*id, rendered))
+            }
+            _ => None,
+        }
+    }
+
+    fn scaled(&self) -> bool {
+        self.render.as_ref().is_some_and(|(.., r)| r.scaled())
+    }
+
+    fn source(&self) -> Option<(u32, u32)> {
+        self.render.as_ref().map(|(.., r)| r.source)
+    }
+
+    fn handle(&self) -> Handle {
+        match &self.render {
+            Some((.., rendered)) => rendered.handle.clone(),
+            None => Handle::from_memory(NORD_SRC.to_vec()),
+        }
     }
 }
 
@@ -51,46 +81,523 @@ pub trait ImageView {
     fn set_loc(&mut self, new: &str);
     fn src(&self) -> &[u8];
 
-    fn view(&self) -> crate::IcedElement {
-        let handle = if self.location().is_empty() {
+    fn handle(&self) -> Handle {
+        if self.location().is_empty() {
             Handle::from_memory(self.src().to_vec())
         } else {
             Handle::from(self.location())
-        };
-        image(handle)
-            .content_fit(ContentFit::Fill)
-            .width(Length::FillPortion(50))
-            .height(Length::FillPortion(50))
-            .into()
+        }
     }
 }
 
-#[derive(Default)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pane {
+    Original,
+    Nordified,
+}
+
+#[derive(Clone, Debug)]
+pub enum PreviewEvent {
+    Zoom(Pane, Zoom),
+    Fit(Pane, ContentFit),
+    Actual(Pane),
+    ToggleLock,
+    ToggleCompare,
+    ToggleDifference,
+    Compared(String, u64, Compared),
+    Split(f32),
+    ToggleInspector,
+    Hover(Option<[f32; 2]>),
+    /// New size of the pane the nordified image is shown in
+    PaneResized(u32, u32),
+    Decoded(String, Decoded),
+    /// Shows one whole image in the comparison pane, alternating between the two
+    Flick,
+}
+
 pub struct Previews {
     pub original: OriginalImage,
     pub nordified: NordifiedImage,
+    original_zoom: Zoom,
+    nordified_zoom: Zoom,
+    /// Both panes zoom and pan together
+    locked: bool,
+    arrangement: Arrangement,
+    split: f32,
+    /// The image shown in full instead of the split, if any
+    flicked: Option<Pane>,
+    /// Location of the last original compared, the preview it was compared with, and
+    /// how they compare
+    difference: Option<(String, u64, Compared)>,
+    inspector: Inspector,
+    /// Palette the nordified image was made with
+    pub palette: Option<Palette>,
+    /// Size of the pane the nordified image is shown in
+    pane: (u32, u32),
+}
+
+/// Colours under the cursor, read from the preview and a decoded copy of the original
+#[derive(Default)]
+struct Inspector {
+    enabled: bool,
+    /// Centre of the hovered pixel relative to the image size
+    hovered: Option<[f32; 2]>,
+    original: Option<(String, Decoded)>,
+}
+
+impl Inspector {
+    fn decoded(&self, loc: &str) -> Option<&Decoded> {
+        match &self.original {
+            Some((l, decoded)) if l == loc => Some(decoded),
+            _ => None,
+        }
+    }
+
+    fn pixel(&self, image: &RgbaImage) -> Option<[u8; 3]> {
+        let [u, v] = self.hovered?;
+        let x = ((u * image.width() as f32) as u32).min(image.width().saturating_sub(1));
+        let y = ((v * image.height() as f32) as u32).min(image.height().saturating_sub(1));
+        image
+            .get_pixel_checked(x, y)
+            .map(|p| [p.0[0], p.0[1], p.0[2]])
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Arrangement {
+    SideBySide,
+    /// Both images overlaid in a single pane, split by a divider
+    Compare,
+    /// The nordified pane shows how far each pixel moved from the original
+    Difference,
+}
+
+impl Default for Previews {
+    fn default() -> Self {
+        Previews {
+            original: OriginalImage::default(),
+            nordified: NordifiedImage::default(),
+            original_zoom: Zoom::default(),
+            nordified_zoom: Zoom::default(),
+            locked: false,
+            arrangement: Arrangement::SideBySide,
+            split: 0.5,
+            flicked: None,
+            difference: None,
+            inspector: Inspector::default(),
+            palette: None,
+            pane: (512, 384),
+        }
+    }
 }
 
 impl Previews {
     pub fn view(&self) -> crate::IcedElement {
-        row!(
-            tooltip(
-                self.original.view(),
-                "Original",
-                tooltip::Position::FollowCursor
-            )
-            .size(16)
-            .style(theme::ContainerType::Tooltip),
-            tooltip(
-                self.nordified.view(),
-                "Nordified",
-                tooltip::Position::FollowCursor
-            )
-            .size(16)
-            .style(theme::ContainerType::Tooltip),
-        )
+        let panes = match self.arrangement {
+            Arrangement::SideBySide => row![
+                self.pane(Pane::Original, "Original"),
+                self.pane(
+                    Pane::Nordified,
+                    if self.nordified.scaled() {
+                        "Nordified (downscaled)"
+                    } else {
+                        "Nordified"
+                    }
+                ),
+            ],
+            Arrangement::Compare => row![self.compare_pane()],
+            Arrangement::Difference => row![
+                self.pane(Pane::Original, "Original"),
+                self.difference_pane(),
+                self.difference_stats(),
+            ],
+        };
+        let panes = panes.spacing(6).height(Length::Fill);
+
+        if self.inspector.enabled {
+            column![panes, self.inspector_bar()]
+        } else {
+            column![panes]
+        }
         .spacing(6)
         .height(Length::FillPortion(50))
         .into()
     }
+
+    pub fn update(&mut self, message: PreviewEvent) {
+        match message {
+            PreviewEvent::Zoom(pane, zoom) => self.set_zoom(pane, zoom),
+            PreviewEvent::Fit(pane, fit) => self.set_zoom(pane, Zoom::fit(fit)),
+            PreviewEvent::Actual(pane) => self.set_zoom(pane, Zoom::actual()),
+            PreviewEvent::ToggleLock => {
+                self.locked = !self.locked;
+                self.nordified_zoom = self.original_zoom;
+            }
+            PreviewEvent::ToggleCompare => {
+                self.arrange(Arrangement::Compare);
+                self.flicked = None;
+            }
+            PreviewEvent::ToggleDifference => self.arrange(Arrangement::Difference),
+            PreviewEvent::Compared(original, nordified, compared) => {
+                self.difference = Some((original, nordified, compared))
+            }
+            PreviewEvent::Split(split) => {
+                self.split = split;
+                self.flicked = None;
+            }
+            PreviewEvent::ToggleInspector => {
+                self.inspector.enabled = !self.inspector.enabled;
+                self.inspector.hovered = None;
+            }
+            PreviewEvent::Hover(hovered) => self.inspector.hovered = hovered,
+            PreviewEvent::PaneResized(width, height) => self.pane = (width, height),
+            PreviewEvent::Decoded(loc, decoded) => self.inspector.original = Some((loc, decoded)),
+            PreviewEvent::Flick => {
+                if self.arrangement == Arrangement::Compare {
+                    self.flicked = match self.flicked {
+                        Some(Pane::Original) => Some(Pane::Nordified),
+                        _ => Some(Pane::Original),
+                    };
+                }
+            }
+        }
+    }
+
+    /// Compares the current images while the difference is on display, and decodes the
+    /// original for the inspector while it's open
+    pub fn subscription(&self) -> Subscription<Event> {
+        let original = self.original.location();
+        let difference = match (self.nordified.of(original), self.current_difference()) {
+            (Some((id, rendered)), None) if self.arrangement == Arrangement::Difference => {
+                process::difference(original.to_string(), id, rendered.pixels.clone())
+                    .map(|(o, id, c)| Event::Preview(PreviewEvent::Compared(o, id, c)))
+            }
+            _ => Subscription::none(),
+        };
+
+        let decode = if !self.inspector.enabled
+            || original.is_empty()
+            || self.inspector.decoded(original).is_some()
+        {
+            Subscription::none()
+        } else {
+            process::decode(original.to_string())
+                .map(|(l, d)| Event::Preview(PreviewEvent::Decoded(l, d)))
+        };
+
+        Subscription::batch([difference, decode])
+    }
+
+    /// Largest image worth rendering for a quick preview: one that fills the pane. It is
+    /// rounded up to steps of [`PANE_STEP`], so nudging the window doesn't make every
+    /// cached preview too small.
+    pub fn max_size(&self) -> (u32, u32) {
+        let step = |side: u32| side.max(1).div_ceil(PANE_STEP) * PANE_STEP;
+        (step(self.pane.0), step(self.pane.1))
+    }
+
+    /// Switches to `arrangement`, or back to side by side if it's already shown
+    fn arrange(&mut self, arrangement: Arrangement) {
+        self.arrangement = if self.arrangement == arrangement {
+            Arrangement::SideBySide
+        } else {
+            arrangement
+        };
+    }
+
+    /// The comparison of the images currently shown, if it has been made
+    fn current_difference(&self) -> Option<&Compared> {
+        match &self.difference {
+            Some((o, id, compared))
+                if o == self.original.location() && Some(*id) == self.nordified.id() =>
+            {
+                Some(compared)
+            }
+            _ => None,
+        }
+    }
+
+    fn zoom(&self, pane: Pane) -> Zoom {
+        match pane {
+            Pane::Original => self.original_zoom,
+            Pane::Nordified => self.nordified_zoom,
+        }
+    }
+
+    fn set_zoom(&mut self, pane: Pane, zoom: Zoom) {
+        if self.locked || pane == Pane::Original {
+            self.original_zoom = zoom;
+        }
+        if self.locked || pane == Pane::Nordified {
+            self.nordified_zoom = zoom;
+        }
+    }
+
+    /// `handle` zoomed like `pane`, reporting the hovered pixel while inspecting
+    fn zoomable(&self, handle: Handle, pane: Pane) -> ZoomImage<'_, Event> {
+        let image = zoom_image(handle, self.zoom(pane), move |z| {
+            Event::Preview(PreviewEvent::Zoom(pane, z))
+        });
+        if self.inspector.enabled {
+            image.on_hover(|p| Event::Preview(PreviewEvent::Hover(p)))
+        } else {
+            image
+        }
+    }
+
+    /// `image` showing the nordified image, or something made from it, sized like the image
+    /// it came from so it lines up with the original
+    fn nordified(&self, image: ZoomImage<'_, Event>) -> ZoomImage<'_, Event> {
+        let image = image.on_resize(|size| {
+            Event::Preview(PreviewEvent::PaneResized(
+                size.width as u32,
+                size.height as u32,
+            ))
+        });
+        match self.nordified.source() {
+            Some(source) => image.source_size(source),
+            None => image,
+        }
+    }
+
+    fn pane(&self, pane: Pane, label: &'static str) -> crate::IcedElement {
+        let image = match pane {
+            Pane::Original => self.zoomable(self.original.handle(), pane),
+            Pane::Nordified => self.nordified(self.zoomable(self.nordified.handle(), pane)),
+        };
+
+        column![
+            self.tools(
+                pane,
+                label,
+                row![tool("LOCK", self.locked, PreviewEvent::ToggleLock)]
+            ),
+            tooltip(image, label, tooltip::Position::FollowCursor)
+                .size(16)
+                .style(theme::ContainerType::Tooltip),
+        ]
+        .spacing(4)
+        .width(Length::FillPortion(50))
+        .height(Length::Fill)
+        .into()
+    }
+
+    /// Original on the left of the divider, nordified on the right
+    fn compare_pane(&self) -> crate::IcedElement {
+        let split = match self.flicked {
+            Some(Pane::Original) => 1.,
+            Some(Pane::Nordified) => 0.,
+            None => self.split,
+        };
+        let image = self.nordified(
+            self.zoomable(self.original.handle(), Pane::Original)
+                .compare(self.nordified.handle(), split),
+        );
+        let image = match self.flicked {
+            Some(_) => image,
+            None => image.on_split(|s| Event::Preview(PreviewEvent::Split(s))),
+        };
+
+        let label = match self.flicked {
+            Some(Pane::Original) => "Original",
+            Some(Pane::Nordified) => "Nordified",
+            None => "Original | Nordified",
+        };
+        column![
+            self.tools(
+                Pane::Original,
+                label,
+                row![tool(
+                    "SPLIT",
+                    self.flicked.is_none(),
+                    PreviewEvent::Split(self.split)
+                )]
+            ),
+            image,
+        ]
+        .spacing(4)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+
+    fn difference_pane(&self) -> crate::IcedElement {
+        let body: crate::IcedElement = match self.current_difference() {
+            Some(Ok((heatmap, ..))) => self
+                .nordified(self.zoomable(heatmap.clone(), Pane::Nordified))
+                .into(),
+            Some(Err(e)) => placeholder(e.clone()),
+            None if self.nordified.of(self.original.location()).is_none() => {
+                placeholder("Preview an image to compare it".into())
+            }
+            None => placeholder("Comparing...".into()),
+        };
+
+        column![
+            self.tools(
+                Pane::Nordified,
+                "Difference",
+                row![tool("LOCK", self.locked, PreviewEvent::ToggleLock)]
+            ),
+            body,
+        ]
+        .spacing(4)
+        .width(Length::FillPortion(50))
+        .height(Length::Fill)
+        .into()
+    }
+
+    /// Mean and max ΔE of the difference
+    fn difference_stats(&self) -> crate::IcedElement {
+        let stat = |label: &'static str, value: Option<f32>| {
+            column![
+                text(label).size(14).style(theme::TextType::Label),
+                text(value.map_or("-".into(), |v| format!("{v:.2}"))).size(20),
+            ]
+            .spacing(2)
+        };
+        let (mean, max) = match self.current_difference() {
+            Some(Ok((_, mean, max))) => (Some(*mean), Some(*max)),
+            _ => (None, None),
+        };
+
+        container(
+            column![stat("MEAN ΔE", mean), stat("MAX ΔE", max)]
+                .spacing(12)
+                .padding(8),
+        )
+        .style(theme::ContainerType::Inner)
+        .width(Length::Units(96))
+        .height(Length::Fill)
+        .into()
+    }
+
+    /// Original and nordified colours of the hovered pixel, and the palette entry nearest
+    /// to the nordified one
+    fn inspector_bar(&self) -> crate::IcedElement {
+        let hex = |[r, g, b]: [u8; 3]| format!("#{r:02x}{g:02x}{b:02x}");
+        let color = |label: &'static str, pixel: Option<[u8; 3]>, status: Option<String>| {
+            let swatch = Color::from_rgb8(
+                pixel.map_or(0, |p| p[0]),
+                pixel.map_or(0, |p| p[1]),
+                pixel.map_or(0, |p| p[2]),
+            );
+            let value = match pixel {
+                Some(p @ [r, g, b]) => format!("{r:3} {g:3} {b:3}  {}", hex(p)),
+                None => status.unwrap_or_else(|| "-".into()),
+            };
+            row![
+                text(label).size(14).style(theme::TextType::Label),
+                container(text(""))
+                    .style(theme::ContainerType::Swatch(swatch))
+                    .width(Length::Units(16))
+                    .height(Length::Units(16)),
+                text(value).size(14),
+            ]
+            .spacing(6)
+            .align_items(iced::Alignment::Center)
+        };
+
+        let loc = self.original.location();
+        let (original, status) = match self.inspector.decoded(loc) {
+            Some(Ok(image)) => (self.inspector.pixel(image), None),
+            Some(Err(e)) => (None, Some(e.clone())),
+            None if !loc.is_empty() => (None, Some("Loading...".to_string())),
+            None => (None, None),
+        };
+        // A preview of another image would pair up unrelated pixels
+        let nordified = self
+            .nordified
+            .of(loc)
+            .and_then(|(_, rendered)| self.inspector.pixel(&rendered.pixels));
+
+        let entry = match (&self.palette, nordified) {
+            (Some(palette), Some(p)) if !palette.colors.is_empty() => {
+                let i = extract::nearest(&palette.colors, p);
+                format!("{} #{} {}", palette.name, i + 1, hex(palette.colors[i]))
+            }
+            _ => "-".into(),
+        };
+
+        container(
+            row![
+                color("ORIGINAL", original, status),
+                color("NORDIFIED", nordified, None),
+                row![
+                    text("PALETTE").size(14).style(theme::TextType::Label),
+                    text(entry).size(14),
+                ]
+                .spacing(6),
+            ]
+            .spacing(24)
+            .align_items(iced::Alignment::Center),
+        )
+        .style(theme::ContainerType::Inner)
+        .padding(6)
+        .width(Length::Fill)
+        .into()
+    }
+
+    /// Fit toggles for `pane`, followed by `extra`
+    fn tools<'a>(
+        &self,
+        pane: Pane,
+        label: &str,
+        extra: iced::widget::Row<'a, Event, iced::Renderer<theme::NordTheme>>,
+    ) -> iced::widget::Row<'a, Event, iced::Renderer<theme::NordTheme>> {
+        let zoom = self.zoom(pane);
+        let fit = |name, fit: ContentFit| {
+            let active = zoom == Zoom::fit(fit);
+            tool(name, active, PreviewEvent::Fit(pane, fit))
+        };
+
+        row![
+            text(label.to_uppercase())
+                .size(14)
+                .style(theme::TextType::Label)
+                .width(Length::Fill),
+            fit("FIT", ContentFit::Contain),
+            fit("FILL", ContentFit::Cover),
+            fit("STRETCH", ContentFit::Fill),
+            tool("1:1", zoom.is_actual(), PreviewEvent::Actual(pane)),
+            extra.spacing(4),
+            tool(
+                "COMPARE",
+                self.arrangement == Arrangement::Compare,
+                PreviewEvent::ToggleCompare
+            ),
+            tool(
+                "DIFF",
+                self.arrangement == Arrangement::Difference,
+                PreviewEvent::ToggleDifference
+            ),
+            tool(
+                "INSPECT",
+                self.inspector.enabled,
+                PreviewEvent::ToggleInspector
+            ),
+        ]
+        .spacing(4)
+        .align_items(iced::Alignment::Center)
+    }
+}
+
+fn placeholder<'a>(message: String) -> crate::IcedElement<'a> {
+    container(text(message).size(16).style(theme::TextType::Label))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x()
+        .center_y()
+        .into()
+}
+
+fn tool<'a>(
+    name: &'static str,
+    active: bool,
+    message: PreviewEvent,
+) -> iced::widget::Button<'a, Event, iced::Renderer<theme::NordTheme>> {
+    button(text(name).size(14))
+        .on_press(Event::Preview(message))
+        .style(theme::ButtonType::Content { selected: active })
+        .padding(2)
 }
@@ -0,0 +1,430 @@
+use iced::futures::{
+    channel::{
+        mpsc::{self, UnboundedReceiver, UnboundedSender},
+        oneshot,
+    },
+    StreamExt,
+};
+use iced::{widget::image::Handle, Subscription};
+use image::RgbaImage;
+use nordify_gui::{
+    diff,
+    watch::{Watch, WatchEvent},
+    Job, NordifyError, Outcome, Stage,
+};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::{
+    path::{Path, PathBuf},
+    sync::{mpsc as std_mpsc, Arc},
+    thread,
+    time::Duration,
+};
+
+use crate::thumbnail;
+
+/// A [`Job`] queued from the GUI
+#[derive(Clone, Debug)]
+pub struct Task {
+    pub id: u64,
+    pub kind: JobKind,
+    pub job: Job,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobKind {
+    /// Images larger than `max` are downscaled first, unless it's `None`
+    Preview {
+        max: Option<(u32, u32)>,
+    },
+    Save,
+}
+
+#[derive(Clone, Debug)]
+pub enum JobEvent {
+    Started(u64),
+    Progress(u64, Stage),
+    Finished(u64, Outcome),
+    /// A preview, which is kept in memory rather than saved
+    Rendered(u64, Rendered),
+    Failed(u64, NordifyError),
+}
+
+/// A processed image, along with a handle the renderer can draw directly
+#[derive(Clone, Debug)]
+pub struct Rendered {
+    pub pixels: Arc<RgbaImage>,
+    pub handle: Handle,
+    /// Size of the image it was made from, which is larger if it was downscaled
+    pub source: (u32, u32),
+}
+
+impl Rendered {
+    pub fn new(pixels: RgbaImage) -> Self {
+        let (width, height) = pixels.dimensions();
+        // The renderer wants BGRA
+        let mut bgra = pixels.as_raw().clone();
+        bgra.chunks_exact_mut(4).for_each(|p| p.swap(0, 2));
+        Rendered {
+            pixels: Arc::new(pixels),
+            handle: Handle::from_pixels(width, height, bgra),
+            source: (width, height),
+        }
+    }
+
+    pub fn scaled(&self) -> bool {
+        self.source != self.pixels.dimensions()
+    }
+
+    /// A copy no larger than `side` on either edge
+    pub fn thumbnail(&self, side: u32) -> Handle {
+        let (width, height) = self.pixels.dimensions();
+        let scale = (side as f32 / width.max(height).max(1) as f32).min(1.);
+        let small = image::imageops::thumbnail(
+            &*self.pixels,
+            ((width as f32 * scale) as u32).max(1),
+            ((height as f32 * scale) as u32).max(1),
+        );
+        Rendered::new(small).handle
+    }
+}
+
+enum Report {
+    Progress(Stage),
+    Done(Outcome),
+    Rendered(Rendered),
+    Failed(NordifyError),
+}
+
+enum State {
+    Ready(Task),
+    Running(u64, UnboundedReceiver<Report>),
+    Done,
+}
+
+impl Task {
+    /// Runs the job on a dedicated thread and reports back through a subscription.
+    /// Dropping the subscription (cancelling, or replacing the task) abandons the job
+    /// at its next stage boundary.
+    pub fn subscription(&self) -> Subscription<JobEvent> {
+        // iced tells subscriptions apart by their id alone, so each kind tags its own
+        iced::subscription::unfold(
+            ("job", self.id),
+            State::Ready(self.clone()),
+            |state| async move {
+                match state {
+                    State::Ready(task) => {
+                        let (tx, rx) = mpsc::unbounded();
+                        let id = task.id;
+                        thread::spawn(move || task.run(tx));
+                        (Some(JobEvent::Started(id)), State::Running(id, rx))
+                    }
+                    State::Running(id, mut rx) => match rx.next().await {
+                        Some(Report::Progress(stage)) => {
+                            (Some(JobEvent::Progress(id, stage)), State::Running(id, rx))
+                        }
+                        Some(Report::Done(outcome)) => {
+                            (Some(JobEvent::Finished(id, outcome)), State::Done)
+                        }
+                        Some(Report::Rendered(rendered)) => {
+                            (Some(JobEvent::Rendered(id, rendered)), State::Done)
+                        }
+                        Some(Report::Failed(e)) => (Some(JobEvent::Failed(id, e)), State::Done),
+                        None => (None, State::Done),
+                    },
+                    State::Done => iced::futures::future::pending().await,
+                }
+            },
+        )
+    }
+
+    fn run(self, tx: UnboundedSender<Report>) {
+        // A closed channel means nobody is waiting for the result anymore
+        let progress = |stage| tx.unbounded_send(Report::Progress(stage)).is_ok();
+        let result = match self.kind {
+            JobKind::Preview { max } => {
+                let pixels = match max {
+                    Some(max) => self.job.preview(max, progress),
+                    None => self.job.render(progress),
+                };
+                pixels.map(|pixels| {
+                    let source =
+                        image::image_dimensions(&self.job.input).unwrap_or(pixels.dimensions());
+                    Report::Rendered(Rendered {
+                        source,
+                        ..Rendered::new(pixels)
+                    })
+                })
+            }
+            JobKind::Save => self.job.execute(progress).map(Report::Done),
+        };
+        let _ = match result {
+            Ok(report) => tx.unbounded_send(report),
+            Err(NordifyError::Cancelled { .. }) => Ok(()),
+            Err(e) => tx.unbounded_send(Report::Failed(e)),
+        };
+    }
+}
+
+/// Runs blocking work on its own thread so it can be awaited from `Command::perform`
+pub async fn background<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> Option<T> {
+    let (tx, rx) = oneshot::channel();
+    thread::spawn(move || {
+        let _ = tx.send(work());
+    });
+    rx.await.ok()
+}
+
+/// Several jobs run one after the other on a single thread
+#[derive(Clone, Debug)]
+pub struct Batch {
+    pub id: u64,
+    pub jobs: Vec<Job>,
+}
+
+#[derive(Clone, Debug)]
+pub enum BatchEvent {
+    Progress(u64, usize, Stage),
+    Done(u64, usize, Result<Outcome, NordifyError>),
+    Finished(u64),
+}
+
+enum BatchState {
+    Ready(Batch),
+    Running(u64, UnboundedReceiver<BatchEvent>),
+    Done,
+}
+
+impl Batch {
+    pub fn subscription(&self) -> Subscription<BatchEvent> {
+        iced::subscription::unfold(
+            ("batch", self.id),
+            BatchState::Ready(self.clone()),
+            |state| async move {
+                match state {
+                    BatchState::Ready(batch) => {
+                        let (tx, rx) = mpsc::unbounded();
+                        let id = batch.id;
+                        thread::spawn(move || batch.run(tx));
+                        (None, BatchState::Running(id, rx))
+                    }
+                    BatchState::Running(id, mut rx) => match rx.next().await {
+                        Some(event @ BatchEvent::Finished(_)) => (Some(event), BatchState::Done),
+                        Some(event) => (Some(event), BatchState::Running(id, rx)),
+                        None => (None, BatchState::Done),
+                    },
+                    BatchState::Done => iced::futures::future::pending().await,
+                }
+            },
+        )
+    }
+
+    fn run(self, tx: UnboundedSender<BatchEvent>) {
+        for (i, job) in self.jobs.iter().enumerate() {
+            let result = job.execute(|stage| {
+                tx.unbounded_send(BatchEvent::Progress(self.id, i, stage))
+                    .is_ok()
+            });
+            if let Err(NordifyError::Cancelled { .. }) = result {
+                return;
+            }
+            if tx
+                .unbounded_send(BatchEvent::Done(self.id, i, result))
+                .is_err()
+            {
+                return;
+            }
+        }
+        let _ = tx.unbounded_send(BatchEvent::Finished(self.id));
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum WatchMsg {
+    Event(u64, WatchEvent),
+    Stopped(u64, Option<NordifyError>),
+}
+
+enum WatchState {
+    Ready(u64, Watch),
+    Running(u64, UnboundedReceiver<WatchMsg>),
+    Done,
+}
+
+/// Watches a folder on its own thread until the subscription is dropped
+pub fn watch(id: u64, watch: &Watch) -> Subscription<WatchMsg> {
+    iced::subscription::unfold(
+        ("watch", id),
+        WatchState::Ready(id, watch.clone()),
+        |state| async move {
+            match state {
+                WatchState::Ready(id, watch) => {
+                    let (tx, rx) = mpsc::unbounded();
+                    thread::spawn(move || {
+                        let result = watch.run(|event| match event {
+                            WatchEvent::Idle => !tx.is_closed(),
+                            event => tx.unbounded_send(WatchMsg::Event(id, event)).is_ok(),
+                        });
+                        let _ = tx.unbounded_send(WatchMsg::Stopped(id, result.err()));
+                    });
+                    (None, WatchState::Running(id, rx))
+                }
+                WatchState::Running(id, mut rx) => match rx.next().await {
+                    Some(msg @ WatchMsg::Stopped(..)) => (Some(msg), WatchState::Done),
+                    Some(msg) => (Some(msg), WatchState::Running(id, rx)),
+                    None => (None, WatchState::Done),
+                },
+                WatchState::Done => iced::futures::future::pending().await,
+            }
+        },
+    )
+}
+
+enum DirState {
+    Ready(PathBuf),
+    Running(PathBuf, UnboundedReceiver<()>),
+    Done,
+}
+
+/// Yields `dir` whenever entries are created, removed or renamed in it. Bursts of
+/// changes are coalesced so that a large copy only triggers a handful of reloads.
+pub fn dir_changes(dir: PathBuf) -> Subscription<PathBuf> {
+    iced::subscription::unfold(
+        ("dir", dir.clone()),
+        DirState::Ready(dir),
+        |state| async move {
+            match state {
+                DirState::Ready(dir) => {
+                    let (tx, rx) = mpsc::unbounded();
+                    let watched = dir.clone();
+                    thread::spawn(move || notify_changes(&watched, tx));
+                    (None, DirState::Running(dir, rx))
+                }
+                DirState::Running(dir, mut rx) => match rx.next().await {
+                    Some(()) => (Some(dir.clone()), DirState::Running(dir, rx)),
+                    None => (None, DirState::Done),
+                },
+                DirState::Done => iced::futures::future::pending().await,
+            }
+        },
+    )
+}
+
+fn notify_changes(dir: &Path, tx: UnboundedSender<()>) {
+    let (ntx, nrx) = std_mpsc::channel();
+    let mut watcher = match notify::recommended_watcher(ntx) {
+        Ok(watcher) => watcher,
+        Err(_) => return,
+    };
+    if watcher.watch(dir, RecursiveMode::NonRecursive).is_err() {
+        return;
+    }
+
+    let relevant = |event: notify::Result<notify::Event>| {
+        event.is_ok_and(|e| !matches!(e.kind, EventKind::Access(_)))
+    };
+    loop {
+        match nrx.recv_timeout(Duration::from_secs(1)) {
+            Ok(event) => {
+                let mut changed = relevant(event);
+                while let Ok(event) = nrx.recv_timeout(Duration::from_millis(250)) {
+                    changed |= relevant(event);
+                }
+                if changed && tx.unbounded_send(()).is_err() {
+                    return;
+                }
+            }
+            Err(std_mpsc::RecvTimeoutError::Timeout) if !tx.is_closed() => (),
+            Err(_) => return,
+        }
+    }
+}
+
+pub type Thumbnail = (PathBuf, Result<PathBuf, String>);
+
+enum ThumbState {
+    Ready(Vec<PathBuf>),
+    Running(UnboundedReceiver<Thumbnail>),
+    Done,
+}
+
+/// Generates thumbnails one after another on a worker thread, in the given order
+pub fn thumbnails(id: u64, paths: &[PathBuf]) -> Subscription<Thumbnail> {
+    iced::subscription::unfold(
+        ("thumbnails", id),
+        ThumbState::Ready(paths.to_vec()),
+        |state| async move {
+            match state {
+                ThumbState::Ready(paths) => {
+                    let (tx, rx) = mpsc::unbounded();
+                    thread::spawn(move || {
+                        for path in paths {
+                            let thumb = thumbnail::thumbnail(&path);
+                            if tx.unbounded_send((path, thumb)).is_err() {
+                                return;
+                            }
+                        }
+                    });
+                    (None, ThumbState::Running(rx))
+                }
+                ThumbState::Running(mut rx) => match rx.next().await {
+                    Some(thumb) => (Some(thumb), ThumbState::Running(rx)),
+                    None => (None, ThumbState::Done),
+                },
+                ThumbState::Done => iced::futures::future::pending().await,
+            }
+        },
+    )
+}
+
+/// A heatmap ready to display, with the mean and max ΔE it shows
+pub type Compared = Result<(Handle, f32, f32), String>;
+
+/// Compares the original image at `original` with preview `id` once, on a worker thread
+pub fn difference(
+    original: String,
+    id: u64,
+    nordified: Arc<RgbaImage>,
+) -> Subscription<(String, u64, Compared)> {
+    iced::subscription::unfold(
+        ("difference", original.clone(), id),
+        Some((original, nordified)),
+        move |state| async move {
+            match state {
+                Some((original, nordified)) => {
+                    let path = original.clone();
+                    let compared = background(move || -> Compared {
+                        let original = image::open(path).map_err(|e| e.to_string())?;
+                        let d = diff::difference(&original.to_rgba8(), &nordified);
+                        let heatmap = Rendered::new(d.heatmap);
+                        Ok((heatmap.handle, d.mean, d.max))
+                    })
+                    .await
+                    .unwrap_or_else(|| Err("comparison stopped unexpectedly".into()));
+                    (Some((original, id, compared)), None)
+                }
+                None => iced::futures::future::pending().await,
+            }
+        },
+    )
+}
+
+pub type Decoded = Result<Arc<RgbaImage>, String>;
+
+/// Decodes the image at `path` once, on a worker thread
+pub fn decode(path: String) -> Subscription<(String, Decoded)> {
+    iced::subscription::unfold(("decode", path.clone()), Some(path), |state| async move {
+        match state {
+            Some(path) => {
+                let p = path.clone();
+                let decoded = background(move || -> Decoded {
+                    let image = image::open(p).map_err(|e| e.to_string())?;
+                    Ok(Arc::new(image.to_rgba8()))
+                })
+                .await
+                .unwrap_or_else(|| Err("decoding stopped unexpectedly".into()));
+                (Some((path, decoded)), None)
+            }
+            None => iced::futures::future::pending().await,
+        }
+    })
+}
@@ -98,6 +98,8 @@ pub enum ContainerType {
     Inner,
     Tooltip,
     Options,
+    Error,
+    Swatch(Color),
 }
 
 impl container::StyleSheet for NordTheme {
@@ -137,6 +139,20 @@ impl container::StyleSheet for NordTheme {
                 border_color: LL_WHITE,
                 ..Default::default()
             },
+            ContainerType::Error => container::Appearance {
+                background: Some(Background::Color(RED)),
+                border_radius: 3.5,
+                border_width: 2.,
+                border_color: RED,
+                text_color: Some(WHITE),
+            },
+            ContainerType::Swatch(color) => container::Appearance {
+                background: Some(Background::Color(*color)),
+                border_radius: 3.5,
+                border_width: 2.,
+                border_color: *color,
+                ..Default::default()
+            },
             _ => container::Appearance::default(),
         }
     }
@@ -235,6 +251,7 @@ impl text_input::StyleSheet for NordTheme {
 pub enum ButtonType {
     MainButton { btype: MainType },
     Content { selected: bool },
+    Swatch { color: Color, selected: bool },
 }
 
 #[derive(Clone, Copy)]
@@ -242,6 +259,7 @@ pub enum MainType {
     Preview,
     Save,
     Reset,
+    Cancel,
 }
 
 impl Default for ButtonType {
@@ -274,6 +292,7 @@ impl button::StyleSheet for NordTheme {
                     MainType::Save => BLUE,
                     MainType::Preview => RED,
                     MainType::Reset => YELLOW,
+                    MainType::Cancel => RED,
                 };
 
                 button::Appearance {
@@ -282,6 +301,17 @@ impl button::StyleSheet for NordTheme {
                     ..Default::default()
                 }
             }
+
+            ButtonType::Swatch { color, selected } => {
+                let border_color = if *selected { WHITE } else { *color };
+                button::Appearance {
+                    background: Some(Background::Color(*color)),
+                    border_radius: 3.5,
+                    border_width: 2.,
+                    border_color,
+                    ..Default::default()
+                }
+            }
         }
     }
 
@@ -297,7 +327,7 @@ impl button::StyleSheet for NordTheme {
             },
             ButtonType::MainButton { btype } => {
                 let text_color = match btype {
-                    MainType::Preview | MainType::Reset => BLUE,
+                    MainType::Preview | MainType::Reset | MainType::Cancel => BLUE,
                     MainType::Save => GREEN,
                 };
                 button::Appearance {
@@ -306,6 +336,16 @@ impl button::StyleSheet for NordTheme {
                     ..Default::default()
                 }
             }
+            ButtonType::Swatch { color, .. } => button::Appearance {
+                border_color: BLUE,
+                ..<Self as button::StyleSheet>::active(
+                    self,
+                    &ButtonType::Swatch {
+                        color: *color,
+                        selected: true,
+                    },
+                )
+            },
         }
     }
 }
@@ -394,6 +434,7 @@ pub enum TextType {
     Other,
     Label,
     Option,
+    Failed,
 }
 
 impl text::StyleSheet for NordTheme {
@@ -407,6 +448,7 @@ impl text::StyleSheet for NordTheme {
             TextType::Option => text::Appearance {
                 color: Some(LD_GREY),
             },
+            TextType::Failed => text::Appearance { color: Some(RED) },
         }
     }
 }
@@ -0,0 +1,101 @@
+//! Image thumbnails, cached on disk following the freedesktop thumbnail spec so that
+//! they are shared with file managers
+
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+/// Edge length of the spec's `normal` size
+pub const SIZE: u32 = 128;
+
+/// Returns the cached thumbnail of `path`, generating it first if it is missing or stale
+pub fn thumbnail(path: &Path) -> Result<PathBuf, String> {
+    let path = path.canonicalize().map_err(|e| e.to_string())?;
+    let uri = uri(&path);
+    let mtime = fs::metadata(&path)
+        .and_then(|m| m.modified())
+        .map_err(|e| e.to_string())?
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| e.to_string())?
+        .as_secs()
+        .to_string();
+    let cache = dirs::cache_dir()
+        .ok_or("no cache directory")?
+        .join("thumbnails")
+        .join("normal")
+        .join(format!("{:x}.png", md5::compute(&uri)));
+
+    if !is_current(&cache, &uri, &mtime) {
+        generate(&path, &cache, &uri, &mtime)?;
+    }
+    Ok(cache)
+}
+
+/// A thumbnail is only valid for the exact file and modification time it was made from
+fn is_current(cache: &Path, uri: &str, mtime: &str) -> bool {
+    let reader = match File::open(cache).map(png::Decoder::new) {
+        Ok(decoder) => match decoder.read_info() {
+            Ok(reader) => reader,
+            Err(_) => return false,
+        },
+        Err(_) => return false,
+    };
+    let text = &reader.info().uncompressed_latin1_text;
+    let get = |key: &str| {
+        text.iter()
+            .find(|chunk| chunk.keyword == key)
+            .map(|chunk| chunk.text.as_str())
+    };
+    get("Thumb::URI") == Some(uri) && get("Thumb::MTime") == Some(mtime)
+}
+
+fn generate(path: &Path, cache: &Path, uri: &str, mtime: &str) -> Result<(), String> {
+    let thumb = image::open(path)
+        .map_err(|e| e.to_string())?
+        .thumbnail(SIZE, SIZE)
+        .to_rgba8();
+
+    if let Some(dir) = cache.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    // Written under a temporary name and renamed, so readers never see a partial file
+    let tmp = cache.with_extension(format!("{}.tmp", std::process::id()));
+    let write = || -> Result<(), png::EncodingError> {
+        let mut encoder = png::Encoder::new(
+            BufWriter::new(File::create(&tmp)?),
+            thumb.width(),
+            thumb.height(),
+        );
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.add_text_chunk("Thumb::URI".into(), uri.into())?;
+        encoder.add_text_chunk("Thumb::MTime".into(), mtime.into())?;
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(thumb.as_raw())?;
+        writer.finish()
+    };
+
+    write()
+        .map_err(|e| e.to_string())
+        .and_then(|_| fs::rename(&tmp, cache).map_err(|e| e.to_string()))
+        .map_err(|e| {
+            let _ = fs::remove_file(&tmp);
+            e
+        })
+}
+
+/// `file://` URI of an absolute path, percent-encoding everything but unreserved characters
+fn uri(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    path.bytes().fold(String::from("file://"), |mut uri, b| {
+        if b.is_ascii_alphanumeric() || b"/-_.~".contains(&b) {
+            uri.push(b as char);
+        } else {
+            uri.push_str(&format!("%{b:02X}"));
+        }
+        uri
+    })
+}
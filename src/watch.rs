@@ -0,0 +1,118 @@
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+use whatsinaname::AboutFile;
+
+use crate::{palette::Palette, Job, Mode, NordifyError, Outcome};
+
+/// How long to wait for a burst of events (e.g. a file still being written) to settle
+const SETTLE: Duration = Duration::from_millis(500);
+const IDLE: Duration = Duration::from_secs(1);
+
+/// Nordifies every image created or modified in `src`, writing the results to `dst`
+#[derive(Clone, Debug)]
+pub struct Watch {
+    pub src: PathBuf,
+    pub dst: PathBuf,
+    pub mode: Mode,
+    pub kval: u8,
+    pub palette: Palette,
+}
+
+#[derive(Clone, Debug)]
+pub enum WatchEvent {
+    /// Nothing happened for a while, a chance to stop watching
+    Idle,
+    Processed(Outcome),
+    Failed(NordifyError),
+}
+
+impl Watch {
+    /// Blocks until `report` returns `false`
+    pub fn run(&self, mut report: impl FnMut(WatchEvent) -> bool) -> Result<(), NordifyError> {
+        let error = |e: notify::Error| NordifyError::Load {
+            path: self.src.clone(),
+            reason: e.to_string(),
+        };
+
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx).map_err(error)?;
+        watcher
+            .watch(&self.src, RecursiveMode::NonRecursive)
+            .map_err(error)?;
+        std::fs::create_dir_all(&self.dst).map_err(|e| NordifyError::Save {
+            path: self.dst.clone(),
+            reason: e.to_string(),
+        })?;
+
+        loop {
+            let first = match rx.recv_timeout(IDLE) {
+                Ok(event) => event,
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    if !report(WatchEvent::Idle) {
+                        return Ok(());
+                    }
+                    continue;
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(()),
+            };
+
+            let mut changed = BTreeSet::new();
+            let mut collect = |event: notify::Result<notify::Event>| {
+                if let Ok(event) = event {
+                    if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                        changed.extend(event.paths.into_iter().filter(|p| self.wanted(p)));
+                    }
+                }
+            };
+            collect(first);
+            while let Ok(event) = rx.recv_timeout(SETTLE) {
+                collect(event);
+            }
+
+            for input in changed {
+                let event = match crate::run(&self.job(input)) {
+                    Ok(outcome) => WatchEvent::Processed(outcome),
+                    Err(e) => WatchEvent::Failed(e),
+                };
+                if !report(event) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn wanted(&self, path: &Path) -> bool {
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        // Our own outputs may land in the watched directory
+        path.is_file()
+            && !path.starts_with(&self.dst)
+            && name.has_extension(&crate::EXT)
+            && !name.get_name().ends_with("_nordified")
+    }
+
+    fn job(&self, input: PathBuf) -> Job {
+        let filename = input
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        Job {
+            output: self
+                .dst
+                .join(format!("{}_nordified.png", filename.get_name())),
+            input,
+            mode: self.mode,
+            kval: self.kval,
+            palette: self.palette.clone(),
+        }
+    }
+}
@@ -0,0 +1,440 @@
+//! An image pane that can be zoomed with the mouse wheel and panned by dragging.
+//! The zoom level lives outside the widget so that several panes can share it.
+
+use iced_native::{
+    event,
+    image::{self, Handle},
+    layout, mouse, renderer,
+    widget::{tree, Tree},
+    Clipboard, Color, ContentFit, Element, Event, Layout, Length, Point, Rectangle, Shell, Size,
+    Vector, Widget,
+};
+
+const MIN_SCALE: f32 = 0.1;
+const MAX_SCALE: f32 = 64.;
+/// Zoom factor applied per wheel notch
+const STEP: f32 = 1.2;
+/// How close to the divider, in screen pixels, a press grabs it instead of panning
+const GRAB: f32 = 6.;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Zoom {
+    pub fit: ContentFit,
+    /// Multiplies the size given by `fit`
+    pub scale: f32,
+    /// Displacement of the image centre from the pane centre, in screen pixels
+    pub offset: Vector,
+}
+
+impl Default for Zoom {
+    fn default() -> Self {
+        Zoom::fit(ContentFit::Contain)
+    }
+}
+
+impl Zoom {
+    pub fn fit(fit: ContentFit) -> Self {
+        Zoom {
+            fit,
+            scale: 1.,
+            offset: Vector::new(0., 0.),
+        }
+    }
+
+    /// One image pixel per screen pixel
+    pub fn actual() -> Self {
+        Zoom::fit(ContentFit::None)
+    }
+
+    pub fn is_actual(&self) -> bool {
+        *self == Zoom::actual()
+    }
+
+    /// Where an image of size `image` is drawn inside `bounds`
+    pub fn rect(&self, image: Size, bounds: Rectangle) -> Rectangle {
+        let fitted = self.fit.fit(image, bounds.size());
+        let (width, height) = (fitted.width * self.scale, fitted.height * self.scale);
+        Rectangle {
+            x: bounds.x + (bounds.width - width) / 2. + self.offset.x,
+            y: bounds.y + (bounds.height - height) / 2. + self.offset.y,
+            width,
+            height,
+        }
+    }
+
+    /// Scales by `factor`, keeping the point `around` (relative to the pane centre) still
+    fn zoomed(self, factor: f32, around: Vector) -> Self {
+        let scale = (self.scale * factor).clamp(MIN_SCALE, MAX_SCALE);
+        let ratio = scale / self.scale;
+        Zoom {
+            scale,
+            offset: around - (around - self.offset) * ratio,
+            ..self
+        }
+    }
+}
+
+pub struct ZoomImage<'a, Message> {
+    handle: Handle,
+    zoom: Zoom,
+    on_change: Box<dyn Fn(Zoom) -> Message + 'a>,
+    compare: Option<Compare<'a, Message>>,
+    on_hover: Option<Box<dyn Fn(Option<[f32; 2]>) -> Message + 'a>>,
+    on_resize: Option<Box<dyn Fn(Size) -> Message + 'a>>,
+    /// Lays the images out as if they were this size instead of their own
+    size: Option<Size<u32>>,
+    width: Length,
+    height: Length,
+}
+
+/// A second image shown to the right of a vertical divider
+struct Compare<'a, Message> {
+    handle: Handle,
+    /// Divider position as a fraction of the pane width
+    split: f32,
+    /// Without it the divider is hidden and can't be dragged
+    on_split: Option<Box<dyn Fn(f32) -> Message + 'a>>,
+}
+
+pub fn zoom_image<'a, Message>(
+    handle: Handle,
+    zoom: Zoom,
+    on_change: impl Fn(Zoom) -> Message + 'a,
+) -> ZoomImage<'a, Message> {
+    ZoomImage {
+        handle,
+        zoom,
+        on_change: Box::new(on_change),
+        compare: None,
+        on_hover: None,
+        on_resize: None,
+        size: None,
+        width: Length::Fill,
+        height: Length::Fill,
+    }
+}
+
+impl<'a, Message> ZoomImage<'a, Message> {
+    /// Overlays `other` on the part of the pane right of `split`
+    pub fn compare(mut self, other: Handle, split: f32) -> Self {
+        self.compare = Some(Compare {
+            handle: other,
+            split,
+            on_split: None,
+        });
+        self
+    }
+
+    /// Shows the divider of [`Self::compare`] and lets it be dragged
+    pub fn on_split(mut self, on_split: impl Fn(f32) -> Message + 'a) -> Self {
+        if let Some(compare) = &mut self.compare {
+            compare.on_split = Some(Box::new(on_split));
+        }
+        self
+    }
+
+    /// Reports the image pixel under the cursor whenever it changes, as the position of
+    /// its centre relative to the image size, so images of other sizes can be looked up
+    pub fn on_hover(mut self, on_hover: impl Fn(Option<[f32; 2]>) -> Message + 'a) -> Self {
+        self.on_hover = Some(Box::new(on_hover));
+        self
+    }
+
+    /// Reports the size of the pane whenever it changes
+    pub fn on_resize(mut self, on_resize: impl Fn(Size) -> Message + 'a) -> Self {
+        self.on_resize = Some(Box::new(on_resize));
+        self
+    }
+
+    /// Sizes the images as if they were `width` by `height`, so a downscaled copy lines up
+    /// with the full-size image it was made from
+    pub fn source_size(mut self, (width, height): (u32, u32)) -> Self {
+        self.size = Some(Size::new(width, height));
+        self
+    }
+
+    /// Size the image at `handle` is laid out at
+    fn image_size<Renderer>(&self, renderer: &Renderer, handle: &Handle) -> Size<u32>
+    where
+        Renderer: image::Renderer<Handle = Handle>,
+    {
+        self.size.unwrap_or_else(|| renderer.dimensions(handle))
+    }
+
+    /// Pixel of the image under `cursor`, and the image size
+    fn pixel(
+        &self,
+        image: Size<u32>,
+        bounds: Rectangle,
+        cursor: Point,
+    ) -> Option<((u32, u32), Size<u32>)> {
+        let rect = self
+            .zoom
+            .rect(Size::new(image.width as f32, image.height as f32), bounds);
+        if !bounds.contains(cursor)
+            || !rect.contains(cursor)
+            || image.width == 0
+            || image.height == 0
+        {
+            return None;
+        }
+        let along = |p: f32, start: f32, len: f32, size: u32| {
+            (((p - start) / len * size as f32) as u32).min(size - 1)
+        };
+        Some((
+            (
+                along(cursor.x, rect.x, rect.width, image.width),
+                along(cursor.y, rect.y, rect.height, image.height),
+            ),
+            image,
+        ))
+    }
+
+    /// Screen x of the divider, if there is a visible one
+    fn divider(&self, bounds: Rectangle) -> Option<f32> {
+        self.compare
+            .as_ref()
+            .filter(|c| c.on_split.is_some())
+            .map(|c| bounds.x + bounds.width * c.split)
+    }
+
+    fn on_divider(&self, bounds: Rectangle, cursor: Point) -> bool {
+        bounds.contains(cursor)
+            && self
+                .divider(bounds)
+                .is_some_and(|x| (cursor.x - x).abs() <= GRAB)
+    }
+}
+
+#[derive(Default)]
+struct State {
+    drag: Drag,
+    /// Last pixel reported to `on_hover`
+    hovered: Option<(u32, u32)>,
+    /// Last size reported to `on_resize`
+    size: Option<Size>,
+}
+
+#[derive(Default)]
+enum Drag {
+    #[default]
+    None,
+    /// Cursor position and offset when the drag started
+    Pan(Point, Vector),
+    Divider,
+}
+
+fn center(bounds: Rectangle) -> Point {
+    Point::new(bounds.x + bounds.width / 2., bounds.y + bounds.height / 2.)
+}
+
+impl<'a, Message, Renderer> Widget<Message, Renderer> for ZoomImage<'a, Message>
+where
+    Renderer: image::Renderer<Handle = Handle>,
+{
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn width(&self) -> Length {
+        self.width
+    }
+
+    fn height(&self) -> Length {
+        self.height
+    }
+
+    fn layout(&self, _renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        layout::Node::new(
+            limits
+                .width(self.width)
+                .height(self.height)
+                .resolve(Size::ZERO),
+        )
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &Renderer,
+        _clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+    ) -> event::Status {
+        let bounds = layout.bounds();
+        let state = tree.state.downcast_mut::<State>();
+
+        if let Some(on_resize) = &self.on_resize {
+            if state.size != Some(bounds.size()) {
+                state.size = Some(bounds.size());
+                shell.publish(on_resize(bounds.size()));
+            }
+        }
+        if let (Event::Mouse(mouse::Event::CursorMoved { position }), Some(on_hover)) =
+            (&event, &self.on_hover)
+        {
+            let pixel = self.pixel(self.image_size(renderer, &self.handle), bounds, *position);
+            if pixel.map(|(p, _)| p) != state.hovered {
+                state.hovered = pixel.map(|(p, _)| p);
+                shell.publish(on_hover(pixel.map(|((x, y), size)| {
+                    [
+                        (x as f32 + 0.5) / size.width as f32,
+                        (y as f32 + 0.5) / size.height as f32,
+                    ]
+                })));
+            }
+        }
+        let drag = &mut state.drag;
+
+        match event {
+            Event::Mouse(mouse::Event::WheelScrolled { delta })
+                if bounds.contains(cursor_position) =>
+            {
+                let notches = match delta {
+                    mouse::ScrollDelta::Lines { y, .. } => y,
+                    mouse::ScrollDelta::Pixels { y, .. } => y / 60.,
+                };
+                let around = cursor_position - center(bounds);
+                shell.publish((self.on_change)(
+                    self.zoom.zoomed(STEP.powf(notches), around),
+                ));
+                event::Status::Captured
+            }
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
+                if bounds.contains(cursor_position) =>
+            {
+                *drag = if self.on_divider(bounds, cursor_position) {
+                    Drag::Divider
+                } else {
+                    Drag::Pan(cursor_position, self.zoom.offset)
+                };
+                event::Status::Captured
+            }
+            Event::Mouse(mouse::Event::CursorMoved { position }) => match drag {
+                Drag::Pan(start, offset) => {
+                    shell.publish((self.on_change)(Zoom {
+                        offset: *offset + (position - *start),
+                        ..self.zoom
+                    }));
+                    event::Status::Captured
+                }
+                Drag::Divider => {
+                    let split = ((position.x - bounds.x) / bounds.width).clamp(0., 1.);
+                    if let Some(on_split) = self.compare.as_ref().and_then(|c| c.on_split.as_ref())
+                    {
+                        shell.publish(on_split(split));
+                    }
+                    event::Status::Captured
+                }
+                Drag::None => event::Status::Ignored,
+            },
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+                if !matches!(drag, Drag::None) =>
+            {
+                *drag = Drag::None;
+                event::Status::Captured
+            }
+            _ => event::Status::Ignored,
+        }
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        _viewport: &Rectangle,
+        _renderer: &Renderer,
+    ) -> mouse::Interaction {
+        let bounds = layout.bounds();
+        match tree.state.downcast_ref::<State>().drag {
+            Drag::Pan(..) => return mouse::Interaction::Grabbing,
+            Drag::Divider => return mouse::Interaction::ResizingHorizontally,
+            Drag::None => (),
+        }
+        if self.on_divider(bounds, cursor_position) {
+            mouse::Interaction::ResizingHorizontally
+        } else if bounds.contains(cursor_position) {
+            mouse::Interaction::Grab
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+
+    fn draw(
+        &self,
+        _tree: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Renderer::Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor_position: Point,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        let draw = |renderer: &mut Renderer, handle: &Handle, clip: Rectangle| {
+            let Size { width, height } = self.image_size(&*renderer, handle);
+            let rect = self
+                .zoom
+                .rect(Size::new(width as f32, height as f32), bounds);
+            renderer.with_layer(clip, |renderer| {
+                image::Renderer::draw(renderer, handle.clone(), rect)
+            });
+        };
+
+        let compare = match &self.compare {
+            Some(compare) => compare,
+            None => return draw(renderer, &self.handle, bounds),
+        };
+        let split = bounds.width * compare.split;
+        draw(
+            renderer,
+            &self.handle,
+            Rectangle {
+                width: split,
+                ..bounds
+            },
+        );
+        draw(
+            renderer,
+            &compare.handle,
+            Rectangle {
+                x: bounds.x + split,
+                width: bounds.width - split,
+                ..bounds
+            },
+        );
+
+        if let Some(x) = self.divider(bounds) {
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds: Rectangle {
+                        x: x - 1.,
+                        width: 2.,
+                        ..bounds
+                    },
+                    border_radius: 0.,
+                    border_width: 0.,
+                    border_color: Color::TRANSPARENT,
+                },
+                Color::WHITE,
+            );
+        }
+    }
+}
+
+impl<'a, Message, Renderer> From<ZoomImage<'a, Message>> for Element<'a, Message, Renderer>
+where
+    Message: 'a,
+    Renderer: image::Renderer<Handle = Handle> + 'a,
+{
+    fn from(image: ZoomImage<'a, Message>) -> Self {
+        Element::new(image)
+    }
+}